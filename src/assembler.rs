@@ -0,0 +1,511 @@
+use std::{collections::HashMap, error, fmt, rc::Rc};
+
+use crate::{
+	bytes::ToBytes,
+	instruction::{Capture, ConstIndex, FunctionIndex, Instruction, Offset, Slot, StringIndex, JUMP_SIZE},
+	value::{
+		function::{Address, Function},
+		number::{
+			float::{Float32, Float64},
+			int::{Int32, Int64},
+		},
+	},
+	ValueType,
+};
+
+/// Assembly that could not be turned into instructions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssembleError {
+	line:    usize,
+	message: String,
+}
+
+impl AssembleError {
+	fn new(line: usize, message: impl Into<String>) -> Self {
+		Self {
+			line,
+			message: message.into(),
+		}
+	}
+
+	/// 1-based line of the offending text.
+	pub fn line(&self) -> usize {
+		self.line
+	}
+
+	pub fn message(&self) -> &str {
+		&self.message
+	}
+}
+
+impl fmt::Display for AssembleError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} on line {}", self.message, self.line)
+	}
+}
+
+impl error::Error for AssembleError {}
+
+/// Output of the assembler.
+#[derive(Debug)]
+pub struct Assembled {
+	pub code:      Vec<Instruction>,
+	pub constants: Vec<ValueType>,
+	pub strings:   Vec<String>,
+	pub functions: Vec<Rc<Function>>,
+}
+
+/// Parse assembly as written by the `Disassembler`:
+///
+/// ```text
+/// ; count to 3
+/// .const 3
+/// .string "i"
+///     PUSH 0
+///     DEFINE_GLOBAL "i"
+/// loop:
+///     LOAD_GLOBAL "i"
+///     PUSH_CONST 0
+///     LESS
+///     JUMP_IF_FALSE end
+///     ...
+///     JUMP loop
+/// end:
+///     HALT
+/// ```
+///
+/// An instruction is its mnemonic followed by its operands. Jumps and functions refer to a `label:` or to an
+/// absolute byte offset written as `@offset`. Each `.const` adds a value to the constant pool, in order, for
+/// `PUSH_CONST` to refer to, and each `.function name/arity entry` adds a function for `PUSH_FUNCTION`. Names are
+/// written as "quoted" strings, or as their index in the string table. Each `.string` adds a name to the string
+/// table, in order, names that aren't listed are added after them. Everything after `;` is a comment. The offset
+/// and source line columns of the disassembler's output are skipped, so its output can be assembled again.
+///
+/// The instructions come with the tables they refer to, turn them into a `Container` or `VM` to run them.
+pub fn assemble(text: &str) -> Result<Assembled, AssembleError> {
+	let mut items = Vec::new();
+	let mut constants = Vec::new();
+	let mut strings = Vec::new();
+	let mut functions = Vec::new();
+	let mut labels = HashMap::new();
+	let mut size = 0;
+
+	for (idx, line) in text.lines().enumerate() {
+		let number = idx + 1;
+		let mut operands = Operands::new(stripComment(line));
+
+		let mut word = operands.word();
+		if word.is_some_and(isOffset) {
+			word = operands.word();
+			if word.is_some_and(|w| w == "|" || w.chars().all(|c| c.is_ascii_digit())) {
+				word = operands.word();
+			}
+		}
+
+		if let Some(label) = word.and_then(|w| w.strip_suffix(':')) {
+			if labels.insert(label.to_string(), size).is_some() {
+				return Err(AssembleError::new(
+					number,
+					format!("label '{label}' is defined more than once"),
+				));
+			}
+			word = operands.word();
+		}
+
+		let Some(mnemonic) = word else {
+			continue;
+		};
+
+		if mnemonic == ".const" {
+			let literal = operands
+				.literal()
+				.and_then(|literal| operands.finish().map(|_| literal))
+				.map_err(|message| AssembleError::new(number, message))?;
+			constants.push(literal);
+			continue;
+		}
+
+		if mnemonic == ".string" {
+			let name = operands
+				.string()
+				.and_then(|name| operands.finish().map(|_| name))
+				.map_err(|message| AssembleError::new(number, message))?;
+			strings.push(name);
+			continue;
+		}
+
+		if mnemonic == ".function" {
+			let function = operands
+				.function()
+				.and_then(|function| operands.finish().map(|_| function))
+				.map_err(|message| AssembleError::new(number, message))?;
+			functions.push((number, function));
+			continue;
+		}
+
+		let item = parse(mnemonic, &mut operands)
+			.and_then(|item| operands.finish().map(|_| item))
+			.map_err(|message| AssembleError::new(number, message))?;
+
+		size += item.size();
+		items.push((number, item));
+	}
+
+	let mut code = Vec::with_capacity(items.len());
+	let mut offset = 0;
+	let mut names: HashMap<String, StringIndex> = HashMap::new();
+	for (index, name) in strings.iter().enumerate().rev() {
+		names.insert(name.clone(), index as StringIndex);
+	}
+
+	for (number, item) in items {
+		let end = offset + item.size();
+
+		let instr = match item {
+			Item::Ready(instr) => instr,
+			Item::Jump(jump, target) => {
+				let distance = resolve(&labels, number, &target)? - end as i64;
+				jump(Offset::try_from(distance).map_err(|_| AssembleError::new(number, "jump is too far"))?)
+			}
+			Item::Named(named, Name::Index(index)) => named(index),
+			Item::Named(named, Name::Text(name)) => {
+				let index = *names.entry(name).or_insert_with_key(|name| {
+					strings.push(name.clone());
+					(strings.len() - 1) as StringIndex
+				});
+				named(index)
+			}
+		};
+
+		code.push(instr);
+		offset = end;
+	}
+
+	let functions = functions
+		.into_iter()
+		.map(|(number, (name, arity, target))| {
+			let entry = Address::try_from(resolve(&labels, number, &target)?)
+				.map_err(|_| AssembleError::new(number, "function entry is out of range"))?;
+
+			Ok(Rc::new(Function::new(name, arity, entry)))
+		})
+		.collect::<Result<_, _>>()?;
+
+	Ok(Assembled {
+		code,
+		constants,
+		strings,
+		functions,
+	})
+}
+
+/// Offset of `target`, for the item on line `number`.
+fn resolve(labels: &HashMap<String, usize>, number: usize, target: &Target) -> Result<i64, AssembleError> {
+	match target {
+		Target::Label(label) => labels
+			.get(label)
+			.map(|offset| *offset as i64)
+			.ok_or_else(|| AssembleError::new(number, format!("undefined label '{label}'"))),
+		Target::Offset(offset) => Ok(*offset),
+	}
+}
+
+enum Target {
+	Label(String),
+	Offset(i64),
+}
+
+/// A name operand, either written out or as its index in the string table.
+enum Name {
+	Text(String),
+	Index(StringIndex),
+}
+
+/// An instruction whose jump target or name index may not be known yet.
+enum Item {
+	Ready(Instruction),
+	Jump(fn(Offset) -> Instruction, Target),
+	Named(fn(StringIndex) -> Instruction, Name),
+}
+
+impl Item {
+	/// Encoded size, which doesn't depend on where a jump goes or which index a name gets.
+	fn size(&self) -> usize {
+		match self {
+			Self::Ready(instr) => instr.bytes().len(),
+			Self::Jump(..) => JUMP_SIZE,
+			Self::Named(named, _) => named(0).bytes().len(),
+		}
+	}
+}
+
+fn parse(mnemonic: &str, operands: &mut Operands) -> Result<Item, String> {
+	let instr = match mnemonic {
+		"HALT" => Instruction::Halt,
+		"PUSH" => Instruction::Push(operands.literal()?),
+		"POP" => Instruction::Pop,
+		"PUSH_CONST" => Instruction::PushConst(operands.number::<ConstIndex>()?),
+		"PUSH_FUNCTION" => Instruction::PushFunction(operands.number::<FunctionIndex>()?),
+		"ADD" => Instruction::Add,
+		"SUB" => Instruction::Sub,
+		"MUL" => Instruction::Mul,
+		"DIV" => Instruction::Div,
+		"NEG" => Instruction::Neg,
+		"NOT" => Instruction::Not,
+		"BIT_NOT" => Instruction::BitNot,
+		"MOD" => Instruction::Mod,
+		"POW" => Instruction::Pow,
+		"BIT_AND" => Instruction::BitAnd,
+		"BIT_OR" => Instruction::BitOr,
+		"BIT_XOR" => Instruction::BitXor,
+		"SHL" => Instruction::Shl,
+		"SHR" => Instruction::Shr,
+		"PRINT" => Instruction::Print,
+		"READ_LINE" => Instruction::ReadLine,
+		"READ_CHAR" => Instruction::ReadChar,
+		"LOAD_LOCAL" => Instruction::LoadLocal(operands.number::<Slot>()?),
+		"STORE_LOCAL" => Instruction::StoreLocal(operands.number::<Slot>()?),
+		"DEFINE_GLOBAL" => return Ok(Item::Named(Instruction::DefineGlobal, operands.name()?)),
+		"LOAD_GLOBAL" => return Ok(Item::Named(Instruction::LoadGlobal, operands.name()?)),
+		"STORE_GLOBAL" => return Ok(Item::Named(Instruction::StoreGlobal, operands.name()?)),
+		"LOAD_UPVALUE" => Instruction::LoadUpvalue(operands.number::<Slot>()?),
+		"STORE_UPVALUE" => Instruction::StoreUpvalue(operands.number::<Slot>()?),
+		"CLOSE_UPVALUE" => Instruction::CloseUpvalue,
+		"JUMP" => return Ok(Item::Jump(Instruction::Jump, operands.target()?)),
+		"JUMP_IF_FALSE" => return Ok(Item::Jump(Instruction::JumpIfFalse, operands.target()?)),
+		"JUMP_IF_TRUE" => return Ok(Item::Jump(Instruction::JumpIfTrue, operands.target()?)),
+		"JUMP_IF_FALSE_OR_POP" => return Ok(Item::Jump(Instruction::JumpIfFalseOrPop, operands.target()?)),
+		"JUMP_IF_TRUE_OR_POP" => return Ok(Item::Jump(Instruction::JumpIfTrueOrPop, operands.target()?)),
+		"CALL" => Instruction::Call(operands.number::<u8>()?),
+		"RETURN" => Instruction::Return,
+		"CLOSURE" => Instruction::Closure(operands.captures()?),
+		"CLASS" => return Ok(Item::Named(Instruction::Class, operands.name()?)),
+		"INHERIT" => Instruction::Inherit,
+		"METHOD" => return Ok(Item::Named(Instruction::Method, operands.name()?)),
+		"GET_PROPERTY" => return Ok(Item::Named(Instruction::GetProperty, operands.name()?)),
+		"SET_PROPERTY" => return Ok(Item::Named(Instruction::SetProperty, operands.name()?)),
+		"GET_SUPER" => return Ok(Item::Named(Instruction::GetSuper, operands.name()?)),
+		"EQUAL" => Instruction::Equal,
+		"NOT_EQUAL" => Instruction::NotEqual,
+		"LESS" => Instruction::Less,
+		"LESS_EQUAL" => Instruction::LessEqual,
+		"GREATER" => Instruction::Greater,
+		"GREATER_EQUAL" => Instruction::GreaterEqual,
+		other => return Err(format!("unknown instruction '{other}'")),
+	};
+
+	Ok(Item::Ready(instr))
+}
+
+/// Operands of a single instruction.
+struct Operands<'a> {
+	rest: &'a str,
+}
+
+impl<'a> Operands<'a> {
+	fn new(text: &'a str) -> Self {
+		Self { rest: text }
+	}
+
+	/// The next run of characters up to whitespace or a comma.
+	fn word(&mut self) -> Option<&'a str> {
+		self.rest = self.rest.trim_start();
+		let end = self
+			.rest
+			.find(|c: char| c.is_whitespace() || c == ',')
+			.unwrap_or(self.rest.len());
+		let (word, rest) = self.rest.split_at(end);
+		self.rest = rest;
+
+		(!word.is_empty()).then_some(word)
+	}
+
+	fn expectWord(&mut self, what: &str) -> Result<&'a str, String> {
+		self.word().ok_or_else(|| format!("expected {what}"))
+	}
+
+	fn number<N: std::str::FromStr>(&mut self) -> Result<N, String> {
+		let word = self.expectWord("a number")?;
+		word.parse().map_err(|_| format!("'{word}' is not a valid number here"))
+	}
+
+	fn string(&mut self) -> Result<String, String> {
+		self.quoted('"')
+	}
+
+	/// A "quoted" name, or a name's index in the string table.
+	fn name(&mut self) -> Result<Name, String> {
+		if self.rest.trim_start().starts_with('"') {
+			return Ok(Name::Text(self.string()?));
+		}
+
+		let word = self.expectWord("a \"quoted\" name or a string index")?;
+		word.parse()
+			.map(Name::Index)
+			.map_err(|_| format!("expected a \"quoted\" name or a string index, found '{word}'"))
+	}
+
+	/// The operand of `.function`: the function's name and arity, followed by its entry.
+	fn function(&mut self) -> Result<(String, u8, Target), String> {
+		let signature = self.expectWord("a function name and arity")?;
+		let (name, arity) = signature
+			.rsplit_once('/')
+			.ok_or_else(|| format!("expected name/arity, found '{signature}'"))?;
+		let arity = arity.parse().map_err(|_| format!("'{arity}' is not a valid arity"))?;
+
+		Ok((name.to_string(), arity, self.target()?))
+	}
+
+	fn target(&mut self) -> Result<Target, String> {
+		let word = self.expectWord("a label or @offset")?;
+
+		match word.strip_prefix('@') {
+			Some(offset) => Ok(Target::Offset(
+				offset.parse().map_err(|_| format!("'{word}' is not a valid offset"))?,
+			)),
+			None => Ok(Target::Label(word.to_string())),
+		}
+	}
+
+	fn captures(&mut self) -> Result<Vec<Capture>, String> {
+		let mut captures = Vec::new();
+
+		while let Some(kind) = self.word() {
+			let isLocal = match kind {
+				"local" => true,
+				"upvalue" => false,
+				other => return Err(format!("expected 'local' or 'upvalue', found '{other}'")),
+			};
+			captures.push(Capture {
+				isLocal,
+				index: self.number()?,
+			});
+
+			self.rest = self.rest.trim_start();
+			match self.rest.strip_prefix(',') {
+				Some(rest) => self.rest = rest,
+				None => break,
+			}
+		}
+
+		Ok(captures)
+	}
+
+	/// The operand of `PUSH` and `.const`, written like the disassembler writes values.
+	fn literal(&mut self) -> Result<ValueType, String> {
+		self.rest = self.rest.trim_start();
+
+		if self.rest.starts_with('"') {
+			return Ok(ValueType::String(self.string()?.into()));
+		}
+		if self.rest.starts_with('\'') {
+			let text = self.quoted('\'')?;
+			let mut chars = text.chars();
+			return match (chars.next(), chars.next()) {
+				(Some(c), None) => Ok(ValueType::Char(c.into())),
+				_ => Err(format!("'{text}' is not a single character")),
+			};
+		}
+
+		let word = self.expectWord("a value")?;
+		let invalid = || format!("'{word}' is not a valid value");
+
+		let value = match word {
+			"null" => ValueType::Null,
+			"true" => ValueType::True,
+			"false" => ValueType::False,
+			_ => {
+				if let Some(int) = word.strip_suffix("i32") {
+					ValueType::Int32(Int32::new(int.parse().map_err(|_| invalid())?))
+				} else if let Some(float) = word.strip_suffix("f32") {
+					ValueType::Float32(Float32::from(float.parse::<f32>().map_err(|_| invalid())?))
+				} else if let Ok(int) = word.parse::<i64>() {
+					ValueType::Int64(Int64::new(int))
+				} else {
+					ValueType::Float64(Float64::from(word.parse::<f64>().map_err(|_| invalid())?))
+				}
+			}
+		};
+
+		Ok(value)
+	}
+
+	/// A string or char literal with Rust style escapes.
+	fn quoted(&mut self, quote: char) -> Result<String, String> {
+		self.rest = self.rest.trim_start();
+
+		let mut chars = self.rest.char_indices();
+		if chars.next().map(|(_, c)| c) != Some(quote) {
+			return Err(format!("expected a {quote}quoted{quote} operand"));
+		}
+
+		let mut text = String::new();
+
+		while let Some((idx, c)) = chars.next() {
+			match c {
+				c if c == quote => {
+					self.rest = &self.rest[idx + 1..];
+					return Ok(text);
+				}
+				'\\' => text.push(match chars.next().map(|(_, c)| c) {
+					Some('n') => '\n',
+					Some('r') => '\r',
+					Some('t') => '\t',
+					Some('0') => '\0',
+					Some('u') => {
+						let rest = &self.rest[idx + 2..];
+						let code = rest
+							.strip_prefix('{')
+							.and_then(|rest| rest.split_once('}'))
+							.and_then(|(hex, _)| u32::from_str_radix(hex, 16).ok())
+							.and_then(char::from_u32)
+							.ok_or("invalid unicode escape")?;
+						for (_, c) in chars.by_ref() {
+							if c == '}' {
+								break;
+							}
+						}
+						code
+					}
+					Some(c @ ('\\' | '"' | '\'')) => c,
+					Some(c) => return Err(format!("unknown escape '\\{c}'")),
+					None => break,
+				}),
+				c => text.push(c),
+			}
+		}
+
+		Err(format!("unterminated {quote}quoted{quote} operand"))
+	}
+
+	fn finish(&self) -> Result<(), String> {
+		match self.rest.trim() {
+			"" => Ok(()),
+			rest => Err(format!("unexpected '{rest}' after the operands")),
+		}
+	}
+}
+
+/// `line` without its comment, if it has one. A `;` inside a string or char literal doesn't start a comment.
+fn stripComment(line: &str) -> &str {
+	let mut quote = None;
+	let mut escaped = false;
+
+	for (idx, c) in line.char_indices() {
+		match (quote, c) {
+			(Some(_), _) if escaped => escaped = false,
+			(Some(_), '\\') => escaped = true,
+			(Some(q), c) if c == q => quote = None,
+			(None, '"' | '\'') => quote = Some(c),
+			(None, ';') => return &line[..idx],
+			_ => {}
+		}
+	}
+
+	line
+}
+
+/// Whether `word` is an offset column of the disassembler's output, as opposed to a label or mnemonic.
+fn isOffset(word: &str) -> bool {
+	word.len() >= 4 && word.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f'))
+}
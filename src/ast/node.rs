@@ -1,12 +1,34 @@
 use super::literal::Literal;
-use crate::{
-	bytes::ToBytes,
-	instruction::{INSTR_HALT, INSTR_PRINT, INSTR_PUSH},
-	token::Token,
-};
+use crate::{span::Span, token::Token};
 
 #[derive(Debug)]
-pub enum Node {
+pub struct Node {
+	kind: NodeKind,
+	span: Span,
+}
+
+impl Node {
+	pub fn new(kind: NodeKind, span: Span) -> Self {
+		Self { kind, span }
+	}
+
+	pub fn kind(&self) -> &NodeKind {
+		&self.kind
+	}
+
+	pub fn span(&self) -> Span {
+		self.span
+	}
+}
+
+impl From<NodeKind> for Node {
+	fn from(kind: NodeKind) -> Self {
+		Self::new(kind, Span::default())
+	}
+}
+
+#[derive(Debug)]
+pub enum NodeKind {
 	/// BinaryExpression(OPERATOR, LEFT, RIGHT)
 	BinaryExpression(Token, Box<Node>, Box<Node>),
 	Block(Vec<Node>),
@@ -15,6 +37,8 @@ pub enum Node {
 	/// DoWhileStatement(BODY, TEST)
 	DoWhileStatement(Box<Node>, Box<Node>),
 	EmptyStatement,
+	/// Placeholder for a piece of source the parser could not make sense of.
+	Error,
 	ExpressionStatement(Box<Node>),
 	ForStatement(Option<Box<Node>>, Option<Box<Node>>, Option<Box<Node>>, Box<Node>),
 	/// Function(NAME, PARAMS, BODY)
@@ -26,6 +50,8 @@ pub enum Node {
 	MemberExpression(bool, Box<Node>, Box<Node>),
 	PrintExpression(Box<Node>),
 	Program(Vec<Node>),
+	ReadCharExpression,
+	ReadLineExpression,
 	ReturnStatement(Option<Box<Node>>),
 	SuperExpression,
 	ThisExpression,
@@ -36,45 +62,3 @@ pub enum Node {
 	/// WhileStatement(TEST, BODY)
 	WhileStatement(Box<Node>, Box<Node>),
 }
-
-impl ToBytes for Node {
-	fn bytes(&self) -> Vec<u8> {
-		let mut bytes = Vec::new();
-
-		match self {
-			Node::BinaryExpression(_, _, _) => todo!(),
-			Node::Block(_) => todo!(),
-			Node::CallExpression(_, _) => todo!(),
-			Node::Class(_, _, _) => todo!(),
-			Node::DoWhileStatement(_, _) => todo!(),
-			Node::EmptyStatement => {}
-			Node::ExpressionStatement(_) => todo!(),
-			Node::ForStatement(_, _, _, _) => todo!(),
-			Node::Function(_, _, _) => todo!(),
-			Node::Identifier(_) => todo!(),
-			Node::IfStatement(_, _, _) => todo!(),
-			Node::Literal(v) => {
-				bytes.push(INSTR_PUSH);
-				bytes.extend(v.bytes());
-			}
-			Node::MemberExpression(_, _, _) => todo!(),
-			Node::PrintExpression(v) => {
-				bytes.extend(v.bytes());
-				bytes.push(INSTR_PRINT);
-			}
-			Node::Program(body) => {
-				body.iter().for_each(|node| bytes.extend(node.bytes()));
-				bytes.push(INSTR_HALT);
-			}
-			Node::ReturnStatement(_) => todo!(),
-			Node::SuperExpression => todo!(),
-			Node::ThisExpression => todo!(),
-			Node::UnaryExpression(_, _) => todo!(),
-			Node::VariableDeclaration(_, _) => todo!(),
-			Node::VariableStatement(_) => todo!(),
-			Node::WhileStatement(_, _) => todo!(),
-		}
-
-		bytes
-	}
-}
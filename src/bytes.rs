@@ -1,7 +1,144 @@
+use std::{error, fmt};
+
 pub trait ToBytes {
 	fn bytes(&self) -> Vec<u8>;
 }
 
-pub(crate) trait FromBytes {
-	fn fromBytes(bytes: &mut Vec<u8>) -> Self;
+pub(crate) trait FromBytes: Sized {
+	/// Decode a value from the front of `bytes`, advancing the reader past it.
+	fn fromBytes(bytes: &mut Reader) -> Result<Self, DecodeError>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeErrorKind {
+	/// The bytes ended even though `needed` more were required.
+	UnexpectedEnd {
+		needed: usize,
+	},
+	/// `tag` doesn't name any of the `expected` kind of item, e.g. an instruction or a value.
+	UnknownTag {
+		expected: &'static str,
+		tag:      u8,
+	},
+	InvalidUtf8,
+	/// A char that isn't a Unicode scalar value.
+	InvalidChar,
+	/// A debug info entry that is out of range.
+	InvalidDebugInfo,
+}
+
+/// Bytecode that could not be decoded, with the offset of the first byte that was wrong.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeError {
+	offset: usize,
+	kind:   DecodeErrorKind,
+}
+
+impl DecodeError {
+	pub fn new(offset: usize, kind: DecodeErrorKind) -> Self {
+		Self { offset, kind }
+	}
+
+	pub fn offset(&self) -> usize {
+		self.offset
+	}
+
+	pub fn kind(&self) -> &DecodeErrorKind {
+		&self.kind
+	}
+}
+
+impl fmt::Display for DecodeErrorKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::UnexpectedEnd { needed } => write!(f, "unexpected end of bytecode, {needed} more bytes expected"),
+			Self::UnknownTag { expected, tag } => write!(f, "unknown {expected} 0x{tag:02x}"),
+			Self::InvalidUtf8 => write!(f, "invalid UTF-8 in string"),
+			Self::InvalidChar => write!(f, "invalid character"),
+			Self::InvalidDebugInfo => write!(f, "invalid debug info"),
+		}
+	}
+}
+
+impl fmt::Display for DecodeError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} at offset {}", self.kind, self.offset)
+	}
+}
+
+impl error::Error for DecodeError {}
+
+/// Cursor over encoded bytes, keeping track of how far it has read so errors can point at the offending byte.
+pub(crate) struct Reader<'a> {
+	bytes:  &'a [u8],
+	offset: usize,
+}
+
+impl<'a> Reader<'a> {
+	/// Read `bytes` starting at `offset`.
+	pub fn at(bytes: &'a [u8], offset: usize) -> Self {
+		Self { bytes, offset }
+	}
+
+	/// Offset of the next byte to read.
+	pub fn offset(&self) -> usize {
+		self.offset
+	}
+
+	pub fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+		let available = self.bytes.len().saturating_sub(self.offset);
+		if available < len {
+			return Err(DecodeError::new(self.offset, DecodeErrorKind::UnexpectedEnd {
+				needed: len - available,
+			}));
+		}
+
+		let taken = &self.bytes[self.offset..self.offset + len];
+		self.offset += len;
+
+		Ok(taken)
+	}
+
+	pub fn takeArray<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+		Ok(self.take(N)?.try_into().unwrap())
+	}
+
+	pub fn takeByte(&mut self) -> Result<u8, DecodeError> {
+		Ok(self.take(1)?[0])
+	}
+
+	pub fn takeString(&mut self) -> Result<String, DecodeError> {
+		let size = u16::from_le_bytes(self.takeArray()?);
+		let start = self.offset;
+		let bytes = self.take(size.into())?;
+
+		String::from_utf8(bytes.to_vec()).map_err(|_| DecodeError::new(start, DecodeErrorKind::InvalidUtf8))
+	}
+
+	/// Chars are stored as their code point (u32, little endian).
+	pub fn takeChar(&mut self) -> Result<char, DecodeError> {
+		let start = self.offset;
+		let code = u32::from_le_bytes(self.takeArray()?);
+
+		char::from_u32(code).ok_or_else(|| DecodeError::new(start, DecodeErrorKind::InvalidChar))
+	}
+
+	/// Error for the tag byte that was just read.
+	pub fn unknownTag(&self, expected: &'static str, tag: u8) -> DecodeError {
+		DecodeError::new(self.offset - 1, DecodeErrorKind::UnknownTag { expected, tag })
+	}
+}
+
+/// Names are stored as their length (u16, little endian) followed by their UTF-8 bytes.
+pub(crate) fn nameBytes(name: &str) -> Vec<u8> {
+	let mut bytes = Vec::with_capacity(2 + name.len());
+
+	bytes.extend((name.len() as u16).to_le_bytes());
+	bytes.extend(name.as_bytes());
+
+	bytes
+}
+
+pub(crate) fn nameFromBytes(bytes: &mut Reader) -> Result<String, DecodeError> {
+	bytes.takeString()
 }
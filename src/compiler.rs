@@ -0,0 +1,837 @@
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{
+	ast::node::{Node, NodeKind},
+	bytes::ToBytes,
+	diagnostic::{
+		Diagnostic,
+		Diagnostics,
+		ERR_DUPLICATE_VARIABLE,
+		ERR_INHERIT_SELF,
+		ERR_INVALID_OPERATOR,
+		ERR_INVALID_SUPER,
+		ERR_INVALID_THIS,
+		ERR_JUMP_TOO_FAR,
+		ERR_RETURN_FROM_INITIALIZER,
+		ERR_RETURN_OUTSIDE_FUNCTION,
+		ERR_SELF_INITIALIZER,
+		ERR_SYNTAX,
+		ERR_TOO_MANY_ARGUMENTS,
+		ERR_TOO_MANY_LOCALS,
+		ERR_UNSUPPORTED,
+	},
+	instruction::{Capture, ConstIndex, FunctionIndex, Instruction, Offset, Slot, StringIndex, JUMP_SIZE},
+	span::Span,
+	token::{symbol::Symbol, tokenkind::TokenKind, Token, TokenValue},
+	value::{
+		function::{Address, Function},
+		Value2,
+	},
+};
+
+type Result<T> = std::result::Result<T, Diagnostic>;
+
+struct Local {
+	name:        String,
+	/// Scope depth the local was declared in.
+	depth:       usize,
+	/// `false` while the local's initializer is being compiled.
+	initialized: bool,
+	/// Whether a closure captures the local, so it has to be moved into an upvalue when it goes out of scope.
+	captured:    bool,
+	span:        Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FunctionKind {
+	Script,
+	Function,
+	Method,
+	/// The `init` method of a class, which always returns `this`.
+	Initializer,
+}
+
+/// Locals of the function being compiled. Slots are relative to the function's call frame.
+struct FunctionScope {
+	kind:     FunctionKind,
+	locals:   Vec<Local>,
+	/// Variables of enclosing functions captured by this one.
+	captures: Vec<Capture>,
+	depth:    usize,
+}
+
+impl FunctionScope {
+	fn new(kind: FunctionKind, locals: Vec<Local>, depth: usize) -> Self {
+		Self {
+			kind,
+			locals,
+			captures: Vec::new(),
+			depth,
+		}
+	}
+}
+
+/// The class whose body is being compiled.
+struct ClassScope {
+	hasSuperclass: bool,
+}
+
+/// How an identifier is accessed at runtime.
+enum Variable {
+	Local(Slot),
+	Upvalue(Slot),
+	Global(String),
+}
+
+/// Compiles a `NodeKind::Program` into instructions.
+///
+/// Branches and loops compile to jumps relative to the end of the jump instruction. Forward jumps are emitted with
+/// a placeholder offset and patched once the size of the code they skip is known.
+///
+/// Variables declared at the top level are globals, looked up by name at runtime. Variables declared inside a
+/// block are locals: they live on the stack, in the slot their initializer's value was pushed to, and are popped
+/// when the block ends.
+///
+/// Function bodies are compiled in place, behind a jump over them. Slot 0 of a function's frame holds the function
+/// itself, followed by its parameters and then its locals. Functions using variables of enclosing functions are
+/// wrapped in a closure capturing them as upvalues.
+///
+/// Methods keep the instance they are called on, `this`, in slot 0. A class with a superclass keeps the superclass
+/// in a local called `super` around its methods, so they can capture it.
+///
+/// Literals are stored once in a constant pool and pushed by their index in it. Names of globals, classes, methods
+/// and properties are stored once in a string table, and functions in a function table.
+pub struct Compiler {
+	code:      Vec<(Instruction, Span)>,
+	constants: Vec<Value2>,
+	/// Index of every constant in `constants`, keyed by its encoding so equal constants are only stored once.
+	indices:   HashMap<Vec<u8>, ConstIndex>,
+	strings:   Vec<String>,
+	/// Index of every name in `strings`.
+	names:     HashMap<String, StringIndex>,
+	/// Every compiled function, pushed by its index in it.
+	table:     Vec<Rc<Function>>,
+	/// Size in bytes of `code` once encoded.
+	size:      usize,
+	/// One scope per function being compiled, the innermost last. The first is the top level.
+	functions: Vec<FunctionScope>,
+	/// Classes being compiled, the innermost last.
+	classes:   Vec<ClassScope>,
+}
+
+/// Output of the compiler.
+#[derive(Debug)]
+pub struct Compiled {
+	/// Every instruction paired with the span of the node it was generated from.
+	pub code:      Vec<(Instruction, Span)>,
+	pub constants: Vec<Value2>,
+	pub strings:   Vec<String>,
+	pub functions: Vec<Rc<Function>>,
+}
+
+/// A forward jump waiting for its target.
+struct Patch {
+	/// Index of the jump in `Compiler::code`.
+	index: usize,
+	/// Byte offset right after the jump, which its offset is relative to.
+	end:   usize,
+}
+
+impl Compiler {
+	pub fn compile(program: &Node) -> std::result::Result<Compiled, Diagnostics> {
+		let mut compiler = Self {
+			code:      Vec::new(),
+			constants: Vec::new(),
+			indices:   HashMap::new(),
+			strings:   Vec::new(),
+			names:     HashMap::new(),
+			table:     Vec::new(),
+			size:      0,
+			functions: vec![FunctionScope::new(FunctionKind::Script, Vec::new(), 0)],
+			classes:   Vec::new(),
+		};
+
+		compiler.node(program)?;
+
+		Ok(Compiled {
+			code:      compiler.code,
+			constants: compiler.constants,
+			strings:   compiler.strings,
+			functions: compiler.table,
+		})
+	}
+
+	fn scope(&self) -> &FunctionScope {
+		self.functions.last().unwrap()
+	}
+
+	fn scopeMut(&mut self) -> &mut FunctionScope {
+		self.functions.last_mut().unwrap()
+	}
+
+	fn emit(&mut self, instr: Instruction, span: Span) {
+		self.size += instr.bytes().len();
+		self.code.push((instr, span));
+	}
+
+	/// Push `value`, from the constant pool unless it is as small as a constant index.
+	fn emitConstant(&mut self, value: Value2, span: Span) {
+		if matches!(value, Value2::Null | Value2::True | Value2::False) {
+			return self.emit(Instruction::Push(value), span);
+		}
+
+		let key = value.bytes();
+		let index = match self.indices.get(&key) {
+			Some(index) => *index,
+			None => {
+				let index = self.constants.len() as ConstIndex;
+				self.constants.push(value);
+				self.indices.insert(key, index);
+				index
+			}
+		};
+
+		self.emit(Instruction::PushConst(index), span);
+	}
+
+	/// Index of `name` in the string table, adding it if it isn't there yet.
+	fn string(&mut self, name: String) -> StringIndex {
+		match self.names.get(&name) {
+			Some(index) => *index,
+			None => {
+				let index = self.strings.len() as StringIndex;
+				self.strings.push(name.clone());
+				self.names.insert(name, index);
+				index
+			}
+		}
+	}
+
+	/// Emit a jump to a target that hasn't been compiled yet, see `patchJump`.
+	fn emitJump(&mut self, jump: fn(Offset) -> Instruction, span: Span) -> Patch {
+		self.emit(jump(0), span);
+
+		Patch {
+			index: self.code.len() - 1,
+			end:   self.size,
+		}
+	}
+
+	/// Point a jump emitted by `emitJump` at the next instruction to be emitted.
+	fn patchJump(&mut self, patch: Patch) -> Result<()> {
+		let (instr, span) = &mut self.code[patch.index];
+		let offset = Offset::try_from(self.size - patch.end).map_err(|_| tooFar(*span))?;
+
+		match instr {
+			Instruction::Jump(o)
+			| Instruction::JumpIfFalse(o)
+			| Instruction::JumpIfTrue(o)
+			| Instruction::JumpIfFalseOrPop(o)
+			| Instruction::JumpIfTrueOrPop(o) => *o = offset,
+			other => unreachable!("cannot patch {other:?}"),
+		}
+
+		Ok(())
+	}
+
+	/// Emit a jump back to the instruction at byte offset `start`.
+	fn emitLoop(&mut self, jump: fn(Offset) -> Instruction, start: usize, span: Span) -> Result<()> {
+		let distance = Offset::try_from(self.size + JUMP_SIZE - start).map_err(|_| tooFar(span))?;
+		self.emit(jump(-distance), span);
+
+		Ok(())
+	}
+
+	fn node(&mut self, node: &Node) -> Result<()> {
+		let span = node.span();
+
+		let unsupported = |what: &str| {
+			Err(Diagnostic::error(ERR_UNSUPPORTED, format!("{what} are not supported yet"))
+				.withPrimary(span, "cannot be compiled yet"))
+		};
+
+		match node.kind() {
+			NodeKind::BinaryExpression(op, a, b) if op.kind() == TokenKind::Symbol(Symbol::Equal) => match a.kind() {
+				NodeKind::Identifier(name) => {
+					self.node(b)?;
+					let instr = match self.resolve(&identifier(name), name.span())? {
+						Variable::Local(slot) => Instruction::StoreLocal(slot),
+						Variable::Upvalue(idx) => Instruction::StoreUpvalue(idx),
+						Variable::Global(name) => Instruction::StoreGlobal(self.string(name)),
+					};
+					self.emit(instr, span);
+				}
+				NodeKind::MemberExpression(false, object, property) => {
+					if let NodeKind::SuperExpression = object.kind() {
+						return Err(Diagnostic::error(ERR_INVALID_SUPER, "cannot assign to a superclass method")
+							.withPrimary(a.span(), "cannot assign to this"));
+					}
+
+					self.node(object)?;
+					self.node(b)?;
+					let name = self.string(nameOf(property));
+					self.emit(Instruction::SetProperty(name), span);
+				}
+				_ => return unsupported("computed member assignments"),
+			},
+			NodeKind::BinaryExpression(op, a, b)
+				if matches!(op.kind(), TokenKind::Compound(Symbol::Ampersand | Symbol::Pipe, _)) =>
+			{
+				// the left operand is the result if it decides the outcome, otherwise it is dropped for the right one
+				self.node(a)?;
+				let end = match op.kind() {
+					TokenKind::Compound(Symbol::Ampersand, _) => self.emitJump(Instruction::JumpIfFalseOrPop, span),
+					_ => self.emitJump(Instruction::JumpIfTrueOrPop, span),
+				};
+				self.node(b)?;
+				self.patchJump(end)?;
+			}
+			NodeKind::BinaryExpression(op, a, b) => {
+				self.node(a)?;
+				self.node(b)?;
+				let i = match op.kind() {
+					TokenKind::Symbol(Symbol::Plus) => Instruction::Add,
+					TokenKind::Symbol(Symbol::Minus) => Instruction::Sub,
+					TokenKind::Symbol(Symbol::Asterisk) => Instruction::Mul,
+					TokenKind::Symbol(Symbol::Slash) => Instruction::Div,
+					TokenKind::Symbol(Symbol::Percent) => Instruction::Mod,
+					TokenKind::Compound(Symbol::Asterisk, Symbol::Asterisk) => Instruction::Pow,
+					TokenKind::Symbol(Symbol::Ampersand) => Instruction::BitAnd,
+					TokenKind::Symbol(Symbol::Pipe) => Instruction::BitOr,
+					TokenKind::Symbol(Symbol::Caret) => Instruction::BitXor,
+					TokenKind::Compound(Symbol::Lesser, Symbol::Lesser) => Instruction::Shl,
+					TokenKind::Compound(Symbol::Greater, Symbol::Greater) => Instruction::Shr,
+					TokenKind::Symbol(Symbol::Lesser) => Instruction::Less,
+					TokenKind::Symbol(Symbol::Greater) => Instruction::Greater,
+					TokenKind::Compound(Symbol::Equal, Symbol::Equal) => Instruction::Equal,
+					TokenKind::Compound(Symbol::Bang, Symbol::Equal) => Instruction::NotEqual,
+					TokenKind::Compound(Symbol::Lesser, Symbol::Equal) => Instruction::LessEqual,
+					TokenKind::Compound(Symbol::Greater, Symbol::Equal) => Instruction::GreaterEqual,
+					other => {
+						return Err(Diagnostic::error(
+							ERR_INVALID_OPERATOR,
+							format!("binary operator {other} is not supported yet"),
+						)
+						.withPrimary(op.span(), "unsupported operator")
+						.withSecondary(span, "in this expression"));
+					}
+				};
+				self.emit(i, span);
+			}
+			NodeKind::Block(body) => {
+				self.beginScope();
+				for n in body.iter() {
+					self.node(n)?;
+				}
+				self.endScope(span);
+			}
+			NodeKind::CallExpression(callee, args) => {
+				self.node(callee)?;
+				for arg in args.iter() {
+					self.node(arg)?;
+				}
+
+				let argc = u8::try_from(args.len()).map_err(|_| {
+					Diagnostic::error(ERR_TOO_MANY_ARGUMENTS, "too many arguments")
+						.withPrimary(args[usize::from(u8::MAX)].span(), "only 255 arguments can be passed")
+				})?;
+				self.emit(Instruction::Call(argc), span);
+			}
+			NodeKind::Class(name, superclass, body) => self.class(name, superclass.as_deref(), body, span)?,
+			NodeKind::DoWhileStatement(body, test) => {
+				let start = self.size;
+				self.node(body)?;
+				self.node(test)?;
+				self.emitLoop(Instruction::JumpIfTrue, start, span)?;
+			}
+			NodeKind::EmptyStatement => {}
+			NodeKind::Error => {
+				return Err(
+					Diagnostic::error(ERR_SYNTAX, "cannot compile a program containing syntax errors")
+						.withPrimary(span, "syntax error"),
+				);
+			}
+			NodeKind::ExpressionStatement(expr) => {
+				self.node(expr)?;
+				self.emit(Instruction::Pop, span);
+			}
+			NodeKind::ForStatement(init, test, update, body) => {
+				// variables declared in the initializer are scoped to the loop
+				self.beginScope();
+				if let Some(init) = init {
+					self.node(init)?;
+				}
+
+				let start = self.size;
+				let exit = match test {
+					Some(test) => {
+						self.node(test)?;
+						Some(self.emitJump(Instruction::JumpIfFalse, span))
+					}
+					None => None,
+				};
+
+				self.node(body)?;
+				if let Some(update) = update {
+					self.node(update)?;
+					self.emit(Instruction::Pop, update.span());
+				}
+				self.emitLoop(Instruction::Jump, start, span)?;
+
+				if let Some(exit) = exit {
+					self.patchJump(exit)?;
+				}
+				self.endScope(span);
+			}
+			NodeKind::Function(name, params, body) => {
+				let isGlobal = self.scope().depth == 0;
+
+				// functions may refer to themselves, so the name is usable before the value exists
+				if !isGlobal {
+					self.declareLocal(nameOf(name), name.span())?;
+					self.markInitialized();
+				}
+
+				self.function(FunctionKind::Function, name, params, body, span)?;
+
+				if isGlobal {
+					let global = self.string(nameOf(name));
+					self.emit(Instruction::DefineGlobal(global), name.span());
+				}
+			}
+			NodeKind::Identifier(name) => {
+				let instr = match self.resolve(&identifier(name), name.span())? {
+					Variable::Local(slot) => Instruction::LoadLocal(slot),
+					Variable::Upvalue(idx) => Instruction::LoadUpvalue(idx),
+					Variable::Global(name) => Instruction::LoadGlobal(self.string(name)),
+				};
+				self.emit(instr, span);
+			}
+			NodeKind::IfStatement(test, consequent, alternate) => {
+				self.node(test)?;
+				let skipConsequent = self.emitJump(Instruction::JumpIfFalse, span);
+				self.node(consequent)?;
+
+				match alternate {
+					Some(alternate) => {
+						let skipAlternate = self.emitJump(Instruction::Jump, span);
+						self.patchJump(skipConsequent)?;
+						self.node(alternate)?;
+						self.patchJump(skipAlternate)?;
+					}
+					None => self.patchJump(skipConsequent)?,
+				}
+			}
+			NodeKind::Literal(lit) => self.emitConstant(lit.value().clone(), span),
+			NodeKind::MemberExpression(false, object, property) => match object.kind() {
+				NodeKind::SuperExpression => {
+					match self.classes.last() {
+						None => {
+							return Err(Diagnostic::error(ERR_INVALID_SUPER, "cannot use 'super' outside of a class")
+								.withPrimary(object.span(), "not inside a method"));
+						}
+						Some(class) if !class.hasSuperclass => {
+							return Err(Diagnostic::error(
+								ERR_INVALID_SUPER,
+								"cannot use 'super' in a class without a superclass",
+							)
+							.withPrimary(object.span(), "class has no superclass"));
+						}
+						Some(_) => {}
+					}
+
+					self.variableGet("this", object.span())?;
+					self.variableGet("super", object.span())?;
+					let name = self.string(nameOf(property));
+					self.emit(Instruction::GetSuper(name), span);
+				}
+				_ => {
+					self.node(object)?;
+					let name = self.string(nameOf(property));
+					self.emit(Instruction::GetProperty(name), span);
+				}
+			},
+			NodeKind::MemberExpression(true, _, _) => return unsupported("computed member expressions"),
+			NodeKind::PrintExpression(value) => {
+				self.node(value)?;
+				self.emit(Instruction::Print, span);
+			}
+			NodeKind::Program(body) => {
+				for n in body.iter() {
+					self.node(n)?;
+				}
+				self.emit(Instruction::Halt, span);
+			}
+			NodeKind::ReadCharExpression => self.emit(Instruction::ReadChar, span),
+			NodeKind::ReadLineExpression => self.emit(Instruction::ReadLine, span),
+			NodeKind::ReturnStatement(value) => {
+				if self.functions.len() == 1 {
+					return Err(
+						Diagnostic::error(ERR_RETURN_OUTSIDE_FUNCTION, "cannot return from top-level code")
+							.withPrimary(span, "not inside a function"),
+					);
+				}
+
+				match value {
+					Some(value) if self.scope().kind == FunctionKind::Initializer => {
+						return Err(Diagnostic::error(
+							ERR_RETURN_FROM_INITIALIZER,
+							"cannot return a value from an initializer",
+						)
+						.withPrimary(value.span(), "initializers always return 'this'"));
+					}
+					Some(value) => {
+						self.node(value)?;
+						self.emit(Instruction::Return, span);
+					}
+					None => self.emitReturn(span),
+				}
+			}
+			NodeKind::SuperExpression => {
+				return Err(
+					Diagnostic::error(ERR_INVALID_SUPER, "'super' can only be used to access a method")
+						.withPrimary(span, "expected '.' and a method name"),
+				);
+			}
+			NodeKind::ThisExpression => {
+				if self.classes.is_empty() {
+					return Err(Diagnostic::error(ERR_INVALID_THIS, "cannot use 'this' outside of a class")
+						.withPrimary(span, "not inside a method"));
+				}
+				self.variableGet("this", span)?;
+			}
+			NodeKind::UnaryExpression(op, operand) => {
+				self.node(operand)?;
+				let i = match op.kind() {
+					TokenKind::Symbol(Symbol::Minus) => Instruction::Neg,
+					TokenKind::Symbol(Symbol::Bang) => Instruction::Not,
+					TokenKind::Symbol(Symbol::Tilde) => Instruction::BitNot,
+					other => {
+						return Err(Diagnostic::error(
+							ERR_INVALID_OPERATOR,
+							format!("unary operator {other} is not supported"),
+						)
+						.withPrimary(op.span(), "unsupported operator"));
+					}
+				};
+				self.emit(i, span);
+			}
+			NodeKind::VariableDeclaration(name, init) => self.variable(name, init)?,
+			NodeKind::VariableStatement(decls) => {
+				for decl in decls.iter() {
+					self.node(decl)?;
+				}
+			}
+			NodeKind::WhileStatement(test, body) => {
+				let start = self.size;
+				self.node(test)?;
+				let exit = self.emitJump(Instruction::JumpIfFalse, span);
+				self.node(body)?;
+				self.emitLoop(Instruction::Jump, start, span)?;
+				self.patchJump(exit)?;
+			}
+		}
+
+		Ok(())
+	}
+
+	fn variable(&mut self, name: &Node, init: &Node) -> Result<()> {
+		if self.scope().depth == 0 {
+			self.node(init)?;
+			let global = self.string(nameOf(name));
+			self.emit(Instruction::DefineGlobal(global), name.span());
+			return Ok(());
+		}
+
+		// the initializer's value is left on the stack, in the slot of the new local
+		self.declareLocal(nameOf(name), name.span())?;
+		self.node(init)?;
+		self.markInitialized();
+
+		Ok(())
+	}
+
+	/// Compile a function and push it, wrapped in a closure if it captures any variables.
+	fn function(&mut self, kind: FunctionKind, name: &Node, params: &[Node], body: &Node, span: Span) -> Result<()> {
+		let arity = u8::try_from(params.len()).map_err(|_| {
+			Diagnostic::error(ERR_TOO_MANY_ARGUMENTS, "too many parameters")
+				.withPrimary(params[usize::from(u8::MAX)].span(), "functions can only take 255 parameters")
+		})?;
+
+		let skip = self.emitJump(Instruction::Jump, span);
+		let entry = Address::try_from(self.size).map_err(|_| tooFar(span))?;
+
+		let callee = Local {
+			name:        String::from(if kind == FunctionKind::Function { "" } else { "this" }),
+			depth:       1,
+			initialized: true,
+			captured:    false,
+			span:        name.span(),
+		};
+		self.functions.push(FunctionScope::new(kind, vec![callee], 1));
+
+		for param in params {
+			self.declareLocal(nameOf(param), param.span())?;
+			self.markInitialized();
+		}
+
+		let NodeKind::Block(statements) = body.kind() else {
+			unreachable!("function bodies are always blocks")
+		};
+		for statement in statements.iter() {
+			self.node(statement)?;
+		}
+
+		self.emitReturn(span);
+
+		let captures = self.functions.pop().unwrap().captures;
+		self.patchJump(skip)?;
+
+		let index = self.table.len() as FunctionIndex;
+		self.table.push(Rc::new(Function::new(nameOf(name), arity, entry)));
+		self.emit(Instruction::PushFunction(index), span);
+		if !captures.is_empty() {
+			self.emit(Instruction::Closure(captures), span);
+		}
+
+		Ok(())
+	}
+
+	/// Return `this` from initializers and `null` from every other function.
+	fn emitReturn(&mut self, span: Span) {
+		if self.scope().kind == FunctionKind::Initializer {
+			self.emit(Instruction::LoadLocal(0), span);
+		} else {
+			self.emit(Instruction::Push(Value2::Null), span);
+		}
+		self.emit(Instruction::Return, span);
+	}
+
+	fn class(&mut self, name: &Node, superclass: Option<&Node>, body: &Node, span: Span) -> Result<()> {
+		let ident = nameOf(name);
+		let isGlobal = self.scope().depth == 0;
+
+		if !isGlobal {
+			self.declareLocal(ident.clone(), name.span())?;
+		}
+		let global = self.string(ident.clone());
+		self.emit(Instruction::Class(global), span);
+		if isGlobal {
+			self.emit(Instruction::DefineGlobal(global), name.span());
+		} else {
+			self.markInitialized();
+		}
+
+		self.classes.push(ClassScope {
+			hasSuperclass: superclass.is_some(),
+		});
+
+		if let Some(superclass) = superclass {
+			if nameOf(superclass) == ident {
+				return Err(
+					Diagnostic::error(ERR_INHERIT_SELF, format!("class '{ident}' cannot inherit from itself"))
+						.withPrimary(superclass.span(), "inherits from itself here"),
+				);
+			}
+
+			// the superclass stays on the stack as `super` while the methods are compiled
+			self.beginScope();
+			self.node(superclass)?;
+			self.declareLocal(String::from("super"), superclass.span())?;
+			self.markInitialized();
+
+			self.variableGet(&ident, name.span())?;
+			self.emit(Instruction::Inherit, superclass.span());
+		}
+
+		self.variableGet(&ident, name.span())?;
+
+		let NodeKind::Block(methods) = body.kind() else {
+			unreachable!("class bodies are always blocks")
+		};
+		for method in methods.iter() {
+			let NodeKind::Function(methodName, params, methodBody) = method.kind() else {
+				return self.node(method);
+			};
+
+			let kind = if nameOf(methodName) == "init" {
+				FunctionKind::Initializer
+			} else {
+				FunctionKind::Method
+			};
+			self.function(kind, methodName, params, methodBody, method.span())?;
+			let methodName = self.string(nameOf(methodName));
+			self.emit(Instruction::Method(methodName), method.span());
+		}
+
+		self.emit(Instruction::Pop, span);
+
+		if superclass.is_some() {
+			self.endScope(span);
+		}
+		self.classes.pop();
+
+		Ok(())
+	}
+
+	/// Add a local called `ident` to the current scope. It can't be read until `markInitialized`.
+	fn declareLocal(&mut self, ident: String, span: Span) -> Result<()> {
+		let scope = self.scope();
+
+		if let Some(previous) = scope
+			.locals
+			.iter()
+			.rev()
+			.take_while(|local| local.depth == scope.depth)
+			.find(|local| local.name == ident)
+		{
+			return Err(Diagnostic::error(
+				ERR_DUPLICATE_VARIABLE,
+				format!("variable '{ident}' is already declared in this scope"),
+			)
+			.withPrimary(span, "declared again here")
+			.withSecondary(previous.span, "first declared here"));
+		}
+
+		if scope.locals.len() > usize::from(Slot::MAX) {
+			return Err(
+				Diagnostic::error(ERR_TOO_MANY_LOCALS, "too many local variables in scope").withPrimary(
+					span,
+					format!("only {} locals can be in scope at once", Slot::MAX as usize + 1),
+				),
+			);
+		}
+
+		let depth = scope.depth;
+		self.scopeMut().locals.push(Local {
+			name: ident,
+			depth,
+			initialized: false,
+			captured: false,
+			span,
+		});
+
+		Ok(())
+	}
+
+	fn markInitialized(&mut self) {
+		self.scopeMut().locals.last_mut().unwrap().initialized = true;
+	}
+
+	fn beginScope(&mut self) {
+		self.scopeMut().depth += 1;
+	}
+
+	/// Pop every local declared in the scope being left.
+	fn endScope(&mut self, span: Span) {
+		let scope = self.scopeMut();
+		scope.depth -= 1;
+
+		let depth = scope.depth;
+		while self.scope().locals.last().is_some_and(|local| local.depth > depth) {
+			let local = self.scopeMut().locals.pop().unwrap();
+			let instr = if local.captured {
+				Instruction::CloseUpvalue
+			} else {
+				Instruction::Pop
+			};
+			self.emit(instr, span);
+		}
+	}
+
+	/// Push the value of the variable `ident`.
+	fn variableGet(&mut self, ident: &str, span: Span) -> Result<()> {
+		let instr = match self.resolve(ident, span)? {
+			Variable::Local(slot) => Instruction::LoadLocal(slot),
+			Variable::Upvalue(idx) => Instruction::LoadUpvalue(idx),
+			Variable::Global(name) => Instruction::LoadGlobal(self.string(name)),
+		};
+		self.emit(instr, span);
+
+		Ok(())
+	}
+
+	fn resolve(&mut self, ident: &str, span: Span) -> Result<Variable> {
+		let level = self.functions.len() - 1;
+
+		if let Some(slot) = self.resolveLocal(level, ident, span)? {
+			Ok(Variable::Local(slot))
+		} else if let Some(idx) = self.resolveUpvalue(level, ident, span)? {
+			Ok(Variable::Upvalue(idx))
+		} else {
+			Ok(Variable::Global(ident.to_string()))
+		}
+	}
+
+	/// Slot of the innermost local called `ident` in the function at `level`.
+	fn resolveLocal(&self, level: usize, ident: &str, span: Span) -> Result<Option<Slot>> {
+		let locals = &self.functions[level].locals;
+
+		match locals.iter().rposition(|local| local.name == ident) {
+			Some(idx) if !locals[idx].initialized => Err(Diagnostic::error(
+				ERR_SELF_INITIALIZER,
+				format!("cannot read local variable '{ident}' in its own initializer"),
+			)
+			.withPrimary(span, "used here")
+			.withSecondary(locals[idx].span, "while declaring it here")),
+			Some(idx) => Ok(Some(idx as Slot)),
+			None => Ok(None),
+		}
+	}
+
+	/// Index of the upvalue through which the function at `level` reaches the local `ident` of an enclosing
+	/// function, capturing it in every function in between.
+	fn resolveUpvalue(&mut self, level: usize, ident: &str, span: Span) -> Result<Option<Slot>> {
+		if level == 0 {
+			return Ok(None);
+		}
+
+		let capture = if let Some(slot) = self.resolveLocal(level - 1, ident, span)? {
+			self.functions[level - 1].locals[usize::from(slot)].captured = true;
+			Capture {
+				isLocal: true,
+				index:   slot,
+			}
+		} else if let Some(idx) = self.resolveUpvalue(level - 1, ident, span)? {
+			Capture {
+				isLocal: false,
+				index:   idx,
+			}
+		} else {
+			return Ok(None);
+		};
+
+		let captures = &mut self.functions[level].captures;
+		if let Some(idx) = captures.iter().position(|c| *c == capture) {
+			return Ok(Some(idx as Slot));
+		}
+
+		if captures.len() > usize::from(Slot::MAX) {
+			return Err(
+				Diagnostic::error(ERR_TOO_MANY_LOCALS, "too many captured variables in function")
+					.withPrimary(span, "cannot capture any more variables"),
+			);
+		}
+
+		captures.push(capture);
+		Ok(Some((captures.len() - 1) as Slot))
+	}
+}
+
+fn tooFar(span: Span) -> Diagnostic {
+	Diagnostic::error(ERR_JUMP_TOO_FAR, "too much code to jump over").withPrimary(span, "jump is too long")
+}
+
+fn nameOf(node: &Node) -> String {
+	match node.kind() {
+		NodeKind::Identifier(token) => identifier(token),
+		other => unreachable!("expected identifier, found {other:?}"),
+	}
+}
+
+fn identifier(token: &Token) -> String {
+	match token.value() {
+		TokenValue::Identifier(name) => name,
+		other => unreachable!("expected identifier, found {other:?}"),
+	}
+}
@@ -0,0 +1,279 @@
+use std::{error, fmt, rc::Rc};
+
+use crate::{
+	assembler::Assembled,
+	ast::AST,
+	bytes::{nameBytes, nameFromBytes, DecodeError, FromBytes, Reader, ToBytes},
+	debuginfo::DebugInfo,
+	diagnostic::Diagnostics,
+	value::function::Function,
+	ValueType,
+	MAGIC_NUMBER,
+};
+
+/// Version of the container layout and the instruction encoding. Files of any other version are rejected.
+pub const FORMAT_VERSION: u16 = 1;
+
+// Flags
+pub const FLAG_DEBUG_INFO: u16 = 0x0001;
+
+// Section: 0x0_
+const SECTION_CODE: u8 = 0x01;
+const SECTION_CONSTANTS: u8 = 0x02;
+const SECTION_STRINGS: u8 = 0x03;
+const SECTION_FUNCTIONS: u8 = 0x04;
+const SECTION_DEBUG: u8 = 0x05;
+
+/// A compiled program as stored in a `.nyb` file:
+///
+/// ```text
+/// magic "nyb" | version: u16 | flags: u16 | section count: u8 | sections...
+/// section: id: u8 | length: u32 | payload
+/// ```
+///
+/// All integers are little endian. Sections with an unknown id are skipped, only the code section is required.
+/// Instructions refer to globals, classes, methods and properties by their index in the string table, and push
+/// functions by their index in the function table. Programs compiled from source carry `DebugInfo`, which
+/// `stripDebugInfo` removes. `FLAG_DEBUG_INFO` is set while they do.
+#[derive(Debug, Default)]
+pub struct Container {
+	code:      Vec<u8>,
+	constants: Vec<ValueType>,
+	strings:   Vec<String>,
+	functions: Vec<Rc<Function>>,
+	debugInfo: Option<DebugInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContainerError {
+	/// The file doesn't start with `MAGIC_NUMBER`.
+	NotBytecode,
+	UnsupportedVersion(u16),
+	/// The header sets flags this version doesn't know about.
+	UnsupportedFlags(u16),
+	MissingSection(&'static str),
+	DuplicateSection(u8),
+	Decode(DecodeError),
+}
+
+impl fmt::Display for ContainerError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::NotBytecode => write!(f, "not a nyooom bytecode file"),
+			Self::UnsupportedVersion(version) => write!(
+				f,
+				"bytecode format version {version} is not supported, expected version {FORMAT_VERSION}"
+			),
+			Self::UnsupportedFlags(flags) => write!(f, "bytecode flags 0x{flags:04x} are not supported"),
+			Self::MissingSection(name) => write!(f, "bytecode file has no {name} section"),
+			Self::DuplicateSection(id) => write!(f, "bytecode file has more than one section 0x{id:02x}"),
+			Self::Decode(e) => write!(f, "{e}"),
+		}
+	}
+}
+
+impl error::Error for ContainerError {}
+
+impl From<DecodeError> for ContainerError {
+	fn from(e: DecodeError) -> Self {
+		Self::Decode(e)
+	}
+}
+
+impl Container {
+	pub fn new(code: Vec<u8>) -> Self {
+		Self {
+			code,
+			..Default::default()
+		}
+	}
+
+	pub fn withConstants(mut self, constants: Vec<ValueType>) -> Self {
+		self.constants = constants;
+		self
+	}
+
+	pub fn withStrings(mut self, strings: Vec<String>) -> Self {
+		self.strings = strings;
+		self
+	}
+
+	pub fn withFunctions(mut self, functions: Vec<Rc<Function>>) -> Self {
+		self.functions = functions;
+		self
+	}
+
+	pub fn withDebugInfo(mut self, debugInfo: DebugInfo) -> Self {
+		self.debugInfo = Some(debugInfo);
+		self
+	}
+
+	/// Drop the debug info, e.g. before shipping a program.
+	pub fn stripDebugInfo(&mut self) {
+		self.debugInfo = None;
+	}
+
+	pub fn code(&self) -> &[u8] {
+		&self.code
+	}
+
+	pub fn constants(&self) -> &[ValueType] {
+		&self.constants
+	}
+
+	pub fn strings(&self) -> &[String] {
+		&self.strings
+	}
+
+	pub fn functions(&self) -> &[Rc<Function>] {
+		&self.functions
+	}
+
+	pub fn debugInfo(&self) -> Option<&DebugInfo> {
+		self.debugInfo.as_ref()
+	}
+
+	pub fn flags(&self) -> u16 {
+		if self.debugInfo.is_some() {
+			FLAG_DEBUG_INFO
+		} else {
+			0
+		}
+	}
+
+	/// Load a container written by `bytes()`.
+	pub fn read(bytes: &[u8]) -> Result<Self, ContainerError> {
+		if !bytes.starts_with(&MAGIC_NUMBER) {
+			return Err(ContainerError::NotBytecode);
+		}
+
+		let mut reader = Reader::at(bytes, MAGIC_NUMBER.len());
+
+		let version = u16::from_le_bytes(reader.takeArray()?);
+		if version != FORMAT_VERSION {
+			return Err(ContainerError::UnsupportedVersion(version));
+		}
+
+		let flags = u16::from_le_bytes(reader.takeArray()?);
+		if flags & !FLAG_DEBUG_INFO != 0 {
+			return Err(ContainerError::UnsupportedFlags(flags & !FLAG_DEBUG_INFO));
+		}
+
+		let count = reader.takeByte()?;
+
+		let mut code = None;
+		let mut container = Self::default();
+		let mut seen = Vec::new();
+
+		for _ in 0..count {
+			let id = reader.takeByte()?;
+			let length = u32::from_le_bytes(reader.takeArray()?) as usize;
+			let start = reader.offset();
+			reader.take(length)?;
+
+			if seen.contains(&id) {
+				return Err(ContainerError::DuplicateSection(id));
+			}
+			seen.push(id);
+
+			// read each section on its own so a section can't run into the next one
+			let mut section = Reader::at(&bytes[..start + length], start);
+
+			match id {
+				SECTION_CODE => code = Some(section.take(length)?.to_vec()),
+				SECTION_CONSTANTS => container.constants = readList(&mut section, ValueType::fromBytes)?,
+				SECTION_STRINGS => container.strings = readList(&mut section, nameFromBytes)?,
+				SECTION_FUNCTIONS => {
+					container.functions = readList(&mut section, |bytes| Function::fromBytes(bytes).map(Rc::new))?
+				}
+				// without the flag, the debug info is ignored like an unknown section
+				SECTION_DEBUG if flags & FLAG_DEBUG_INFO != 0 => {
+					container.debugInfo = Some(DebugInfo::fromBytes(&mut section)?)
+				}
+				_ => {}
+			}
+		}
+
+		container.code = code.ok_or(ContainerError::MissingSection("code"))?;
+		if flags & FLAG_DEBUG_INFO != 0 && container.debugInfo.is_none() {
+			return Err(ContainerError::MissingSection("debug info"));
+		}
+
+		Ok(container)
+	}
+}
+
+impl ToBytes for Container {
+	fn bytes(&self) -> Vec<u8> {
+		let mut sections = vec![(SECTION_CODE, self.code.clone())];
+
+		if !self.constants.is_empty() {
+			sections.push((SECTION_CONSTANTS, listBytes(&self.constants, ToBytes::bytes)));
+		}
+		if !self.strings.is_empty() {
+			sections.push((SECTION_STRINGS, listBytes(&self.strings, |s| nameBytes(s))));
+		}
+		if !self.functions.is_empty() {
+			sections.push((SECTION_FUNCTIONS, listBytes(&self.functions, |f| f.bytes())));
+		}
+		if let Some(debugInfo) = &self.debugInfo {
+			sections.push((SECTION_DEBUG, debugInfo.bytes()));
+		}
+
+		let mut bytes = MAGIC_NUMBER.to_vec();
+		bytes.extend(FORMAT_VERSION.to_le_bytes());
+		bytes.extend(self.flags().to_le_bytes());
+		bytes.push(sections.len() as u8);
+
+		for (id, payload) in sections {
+			bytes.push(id);
+			bytes.extend((payload.len() as u32).to_le_bytes());
+			bytes.extend(payload);
+		}
+
+		bytes
+	}
+}
+
+impl TryFrom<AST> for Container {
+	type Error = Diagnostics;
+
+	fn try_from(ast: AST) -> Result<Self, Self::Error> {
+		let compiled = ast.compile()?;
+		let mut code = Vec::new();
+		let mut debugInfo = DebugInfo::new();
+
+		for (instr, span) in compiled.code.iter() {
+			debugInfo.push(code.len(), span.line(), span.column());
+			code.extend(instr.bytes());
+		}
+
+		Ok(Self::new(code)
+			.withConstants(compiled.constants)
+			.withStrings(compiled.strings)
+			.withFunctions(compiled.functions)
+			.withDebugInfo(debugInfo))
+	}
+}
+
+impl From<Assembled> for Container {
+	fn from(assembled: Assembled) -> Self {
+		Self::new(assembled.code.iter().flat_map(ToBytes::bytes).collect())
+			.withConstants(assembled.constants)
+			.withStrings(assembled.strings)
+			.withFunctions(assembled.functions)
+	}
+}
+
+/// Lists are stored as their length (u32) followed by their items.
+fn listBytes<T>(items: &[T], itemBytes: impl Fn(&T) -> Vec<u8>) -> Vec<u8> {
+	let mut bytes = (items.len() as u32).to_le_bytes().to_vec();
+	bytes.extend(items.iter().flat_map(itemBytes));
+
+	bytes
+}
+
+fn readList<T>(bytes: &mut Reader, item: impl Fn(&mut Reader) -> Result<T, DecodeError>) -> Result<Vec<T>, DecodeError> {
+	let count = u32::from_le_bytes(bytes.takeArray()?);
+	(0..count).map(|_| item(bytes)).collect()
+}
@@ -0,0 +1,192 @@
+use crate::{
+	bytes::{nameBytes, nameFromBytes, DecodeError, DecodeErrorKind, FromBytes, Reader, ToBytes},
+	span::{Position, Span, TokenColumn},
+	token::TokenLine,
+};
+
+/// Where in the source the code at a byte offset came from, until the next entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Entry {
+	offset: usize,
+	line:   TokenLine,
+	column: TokenColumn,
+}
+
+/// Table mapping bytecode offsets to the source line and column they were compiled from. Only offsets where the
+/// location changes are stored, so consecutive instructions of the same expression share an entry.
+///
+/// Encoded as the name of the source file (empty if unknown) and the number of entries (u32), followed by each
+/// entry's offset and line as the difference to the previous entry, and its column, all as LEB128 varints. Line
+/// differences are zigzag encoded since code can go back to an earlier line, e.g. at the end of a loop.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DebugInfo {
+	file:    Option<String>,
+	entries: Vec<Entry>,
+}
+
+impl DebugInfo {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Name of the source file the program was compiled from.
+	pub fn withFile(mut self, file: impl Into<String>) -> Self {
+		self.file = Some(file.into());
+		self
+	}
+
+	pub fn file(&self) -> Option<&str> {
+		self.file.as_deref()
+	}
+
+	/// Record that the code from `offset` on comes from `line` and `column`.
+	///
+	/// # Panics
+	///
+	/// If `offset` is before the offset of the previous entry, the table is stored as differences between offsets.
+	pub fn push(&mut self, offset: usize, line: TokenLine, column: TokenColumn) {
+		if let Some(last) = self.entries.last() {
+			assert!(
+				offset >= last.offset,
+				"debug info offset {offset} pushed after offset {}",
+				last.offset
+			);
+			if (last.line, last.column) == (line, column) {
+				return;
+			}
+			if last.offset == offset {
+				self.entries.pop();
+			}
+		}
+
+		self.entries.push(Entry { offset, line, column });
+	}
+
+	/// Line and column of the code at `offset`.
+	pub fn location(&self, offset: usize) -> Option<(TokenLine, TokenColumn)> {
+		let idx = self.entries.partition_point(|entry| entry.offset <= offset);
+		let entry = self.entries.get(idx.checked_sub(1)?)?;
+
+		Some((entry.line, entry.column))
+	}
+
+	/// A span pointing at the start of the code at `offset`. The source offset isn't known, so it is 0.
+	pub fn span(&self, offset: usize) -> Option<Span> {
+		let (line, column) = self.location(offset)?;
+		let position = Position::new(line, column, 0);
+
+		Some(Span::new(position, position))
+	}
+
+	/// Offset and line of every entry, for the `Disassembler`.
+	pub fn lines(&self) -> Vec<(usize, TokenLine)> {
+		self.entries.iter().map(|entry| (entry.offset, entry.line)).collect()
+	}
+
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+}
+
+impl From<&[(usize, Span)]> for DebugInfo {
+	fn from(spans: &[(usize, Span)]) -> Self {
+		let mut debugInfo = Self::new();
+
+		for (offset, span) in spans {
+			debugInfo.push(*offset, span.line(), span.column());
+		}
+
+		debugInfo
+	}
+}
+
+impl ToBytes for DebugInfo {
+	fn bytes(&self) -> Vec<u8> {
+		let mut bytes = nameBytes(self.file.as_deref().unwrap_or_default());
+		bytes.extend((self.entries.len() as u32).to_le_bytes());
+		let mut previous = Entry {
+			offset: 0,
+			line:   0,
+			column: 0,
+		};
+
+		for entry in self.entries.iter() {
+			let line = i64::from(entry.line) - i64::from(previous.line);
+
+			varintBytes(&mut bytes, (entry.offset - previous.offset) as u64);
+			varintBytes(&mut bytes, ((line << 1) ^ (line >> 63)) as u64);
+			varintBytes(&mut bytes, entry.column.into());
+
+			previous = *entry;
+		}
+
+		bytes
+	}
+}
+
+impl FromBytes for DebugInfo {
+	fn fromBytes(bytes: &mut Reader) -> Result<Self, DecodeError> {
+		let file = Some(nameFromBytes(bytes)?).filter(|file| !file.is_empty());
+		let count = u32::from_le_bytes(bytes.takeArray()?);
+		let mut entries = Vec::new();
+		let mut previous = Entry {
+			offset: 0,
+			line:   0,
+			column: 0,
+		};
+
+		for _ in 0..count {
+			let start = bytes.offset();
+			let offset = varintFromBytes(bytes)?;
+			let line = varintFromBytes(bytes)?;
+			let line = (line >> 1) as i64 ^ -((line & 1) as i64);
+			let column = varintFromBytes(bytes)?;
+
+			let invalid = || DecodeError::new(start, DecodeErrorKind::InvalidDebugInfo);
+			let entry = Entry {
+				offset: usize::try_from(offset)
+					.ok()
+					.and_then(|offset| previous.offset.checked_add(offset))
+					.ok_or_else(invalid)?,
+				line:   i64::from(previous.line)
+					.checked_add(line)
+					.and_then(|line| TokenLine::try_from(line).ok())
+					.ok_or_else(invalid)?,
+				column: TokenColumn::try_from(column).map_err(|_| invalid())?,
+			};
+
+			entries.push(entry);
+			previous = entry;
+		}
+
+		Ok(Self { file, entries })
+	}
+}
+
+fn varintBytes(bytes: &mut Vec<u8>, mut value: u64) {
+	while value >= 0x80 {
+		bytes.push(value as u8 | 0x80);
+		value >>= 7;
+	}
+	bytes.push(value as u8);
+}
+
+fn varintFromBytes(bytes: &mut Reader) -> Result<u64, DecodeError> {
+	let start = bytes.offset();
+	let mut value = 0;
+
+	for shift in (0..64).step_by(7) {
+		let byte = bytes.takeByte()?;
+		value |= u64::from(byte & 0x7f) << shift;
+
+		if byte & 0x80 == 0 {
+			return Ok(value);
+		}
+	}
+
+	Err(DecodeError::new(start, DecodeErrorKind::InvalidDebugInfo))
+}
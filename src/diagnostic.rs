@@ -0,0 +1,367 @@
+use std::{error, fmt, io, ops::Deref};
+
+use crate::span::Span;
+
+pub type ErrorCode = &'static str;
+
+// Tokenizer: E01__
+pub const ERR_INVALID_CHARACTER: ErrorCode = "E0101";
+pub const ERR_INVALID_NUMBER: ErrorCode = "E0102";
+pub const ERR_INVALID_CHAR: ErrorCode = "E0103";
+pub const ERR_INVALID_ESCAPE: ErrorCode = "E0104";
+pub const ERR_UNTERMINATED_STRING: ErrorCode = "E0105";
+pub const ERR_UNTERMINATED_COMMENT: ErrorCode = "E0106";
+
+// Parser: E02__
+pub const ERR_UNEXPECTED_TOKEN: ErrorCode = "E0201";
+pub const ERR_INVALID_ASSIGNMENT: ErrorCode = "E0202";
+pub const ERR_INVALID_LITERAL: ErrorCode = "E0203";
+pub const ERR_SYNTAX: ErrorCode = "E0204";
+
+// Compiler: E03__
+pub const ERR_UNSUPPORTED: ErrorCode = "E0301";
+pub const ERR_INVALID_OPERATOR: ErrorCode = "E0302";
+pub const ERR_DUPLICATE_VARIABLE: ErrorCode = "E0303";
+pub const ERR_SELF_INITIALIZER: ErrorCode = "E0304";
+pub const ERR_TOO_MANY_LOCALS: ErrorCode = "E0305";
+pub const ERR_JUMP_TOO_FAR: ErrorCode = "E0306";
+pub const ERR_RETURN_OUTSIDE_FUNCTION: ErrorCode = "E0307";
+pub const ERR_TOO_MANY_ARGUMENTS: ErrorCode = "E0308";
+pub const ERR_INVALID_THIS: ErrorCode = "E0309";
+pub const ERR_INVALID_SUPER: ErrorCode = "E0310";
+pub const ERR_INHERIT_SELF: ErrorCode = "E0311";
+pub const ERR_RETURN_FROM_INITIALIZER: ErrorCode = "E0312";
+
+// VM: E04__
+pub const ERR_INVALID_OPERATION: ErrorCode = "E0401";
+pub const ERR_INVALID_EXIT_CODE: ErrorCode = "E0402";
+pub const ERR_MISSING_HALT: ErrorCode = "E0403";
+pub const ERR_UNKNOWN_INSTRUCTION: ErrorCode = "E0404";
+pub const ERR_UNDEFINED_VARIABLE: ErrorCode = "E0405";
+pub const ERR_INVALID_JUMP: ErrorCode = "E0406";
+pub const ERR_NOT_CALLABLE: ErrorCode = "E0407";
+pub const ERR_ARITY_MISMATCH: ErrorCode = "E0408";
+pub const ERR_STACK_OVERFLOW: ErrorCode = "E0409";
+pub const ERR_UNDEFINED_PROPERTY: ErrorCode = "E0410";
+pub const ERR_INVALID_BYTECODE: ErrorCode = "E0411";
+pub const ERR_DIVISION_BY_ZERO: ErrorCode = "E0412";
+pub const ERR_STACK_UNDERFLOW: ErrorCode = "E0413";
+pub const ERR_OUTPUT: ErrorCode = "E0414";
+pub const ERR_INPUT: ErrorCode = "E0415";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+	Error,
+	Warning,
+	Note,
+}
+
+impl fmt::Display for Severity {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Error => write!(f, "error"),
+			Self::Warning => write!(f, "warning"),
+			Self::Note => write!(f, "note"),
+		}
+	}
+}
+
+/// A span of source text with a message describing its role in a `Diagnostic`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+	span:    Span,
+	message: String,
+}
+
+impl Label {
+	pub fn new(span: Span, message: impl Into<String>) -> Self {
+		Self {
+			span,
+			message: message.into(),
+		}
+	}
+
+	pub fn span(&self) -> Span {
+		self.span
+	}
+
+	pub fn message(&self) -> &str {
+		&self.message
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+	severity:  Severity,
+	code:      ErrorCode,
+	message:   String,
+	primary:   Option<Label>,
+	secondary: Vec<Label>,
+	notes:     Vec<String>,
+	help:      Option<String>,
+}
+
+impl Diagnostic {
+	pub fn new(severity: Severity, code: ErrorCode, message: impl Into<String>) -> Self {
+		Self {
+			severity,
+			code,
+			message: message.into(),
+			primary: None,
+			secondary: Vec::new(),
+			notes: Vec::new(),
+			help: None,
+		}
+	}
+
+	pub fn error(code: ErrorCode, message: impl Into<String>) -> Self {
+		Self::new(Severity::Error, code, message)
+	}
+
+	pub fn warning(code: ErrorCode, message: impl Into<String>) -> Self {
+		Self::new(Severity::Warning, code, message)
+	}
+
+	pub fn withPrimary(mut self, span: Span, label: impl Into<String>) -> Self {
+		self.primary = Some(Label::new(span, label));
+		self
+	}
+
+	pub fn withSecondary(mut self, span: Span, label: impl Into<String>) -> Self {
+		self.secondary.push(Label::new(span, label));
+		self
+	}
+
+	pub fn withNote(mut self, note: impl Into<String>) -> Self {
+		self.notes.push(note.into());
+		self
+	}
+
+	pub fn withHelp(mut self, help: impl Into<String>) -> Self {
+		self.help = Some(help.into());
+		self
+	}
+
+	pub fn severity(&self) -> Severity {
+		self.severity
+	}
+
+	pub fn code(&self) -> ErrorCode {
+		self.code
+	}
+
+	pub fn message(&self) -> &str {
+		&self.message
+	}
+
+	pub fn primary(&self) -> Option<&Label> {
+		self.primary.as_ref()
+	}
+
+	pub fn secondary(&self) -> &[Label] {
+		&self.secondary
+	}
+
+	pub fn notes(&self) -> &[String] {
+		&self.notes
+	}
+
+	pub fn help(&self) -> Option<&str> {
+		self.help.as_deref()
+	}
+
+	pub fn isError(&self) -> bool {
+		self.severity == Severity::Error
+	}
+
+	/// Render the diagnostic together with the offending lines of `source`, underlining the primary label with
+	/// `^` and secondary labels with `-`:
+	///
+	/// ```text
+	/// error[E0201]: expected ';' after expression, found end of file
+	///  --> main.ny:1:10
+	///   |
+	/// 1 | let x = 1
+	///   |          ^ expected ';'
+	///   |
+	///   = help: ...
+	/// ```
+	pub fn render(&self, source: &str, file: &str) -> String {
+		let mut out = format!("{}[{}]: {}\n", self.severity, self.code, self.message);
+
+		let mut labels: Vec<(&Label, char)> = self.primary.iter().map(|label| (label, '^')).collect();
+		labels.extend(self.secondary.iter().map(|label| (label, '-')));
+		labels.sort_by_key(|(label, _)| label.span.start());
+
+		let width = labels
+			.iter()
+			.map(|(label, _)| label.span.line().to_string().len())
+			.max()
+			.unwrap_or(0);
+		let pad = " ".repeat(width);
+
+		if let Some(primary) = &self.primary {
+			out += &format!("{pad}--> {file}:{}\n", primary.span);
+			out += &format!("{pad} |\n");
+		}
+
+		let lines: Vec<&str> = source.lines().collect();
+		let mut lastLine = None;
+
+		for (label, marker) in labels {
+			let lineNo = label.span.line();
+			let text = lines.get((lineNo as usize).saturating_sub(1)).copied().unwrap_or("");
+
+			if lastLine != Some(lineNo) {
+				out += &format!("{lineNo:>width$} | {text}\n");
+				lastLine = Some(lineNo);
+			}
+
+			// columns count bytes, the underline counts characters
+			let bytes = text.as_bytes();
+			let column = (label.span.column() as usize).saturating_sub(1).min(bytes.len());
+			let end = if label.span.endLine() == lineNo {
+				(label.span.endColumn() as usize).saturating_sub(1).max(column)
+			} else {
+				bytes.len()
+			};
+			let length = (charCount(&bytes[column..end.min(bytes.len())]) + end.saturating_sub(bytes.len())).max(1);
+
+			// keep tabs so the underline lines up with the source line
+			let indent: String = bytes[..column]
+				.iter()
+				.filter(|b| !isContinuation(**b))
+				.map(|b| if *b == b'\t' { '\t' } else { ' ' })
+				.collect();
+			let underline = marker.to_string().repeat(length);
+
+			out += format!("{pad} | {indent}{underline} {}", label.message).trim_end();
+			out += "\n";
+		}
+
+		if self.primary.is_some() && (!self.notes.is_empty() || self.help.is_some()) {
+			out += &format!("{pad} |\n");
+		}
+
+		for note in &self.notes {
+			out += &format!("{pad} = note: {note}\n");
+		}
+
+		if let Some(help) = &self.help {
+			out += &format!("{pad} = help: {help}\n");
+		}
+
+		out
+	}
+}
+
+/// Whether `byte` continues a multi-byte UTF-8 character rather than starting one.
+fn isContinuation(byte: u8) -> bool {
+	byte & 0xc0 == 0x80
+}
+
+/// Number of characters in `bytes`, even if they were cut off in the middle of one.
+fn charCount(bytes: &[u8]) -> usize {
+	bytes.iter().filter(|b| !isContinuation(**b)).count()
+}
+
+impl fmt::Display for Diagnostic {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}[{}]: {}", self.severity, self.code, self.message)?;
+
+		if let Some(primary) = &self.primary {
+			write!(f, " ({})", primary.span)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl error::Error for Diagnostic {}
+
+impl From<Diagnostic> for io::Error {
+	fn from(diagnostic: Diagnostic) -> Self {
+		io::Error::other(diagnostic)
+	}
+}
+
+/// Every diagnostic reported by one pass over a program.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Diagnostics(Vec<Diagnostic>);
+
+impl Diagnostics {
+	pub fn new() -> Self {
+		Self(Vec::new())
+	}
+
+	pub fn push(&mut self, diagnostic: Diagnostic) {
+		self.0.push(diagnostic);
+	}
+
+	pub fn extend(&mut self, diagnostics: Diagnostics) {
+		self.0.extend(diagnostics.0);
+	}
+
+	pub fn hasErrors(&self) -> bool {
+		self.0.iter().any(Diagnostic::isError)
+	}
+
+	/// Render every diagnostic, see `Diagnostic::render`.
+	pub fn render(&self, source: &str, file: &str) -> String {
+		self.0
+			.iter()
+			.map(|diagnostic| diagnostic.render(source, file))
+			.collect::<Vec<_>>()
+			.join("\n")
+	}
+}
+
+impl Deref for Diagnostics {
+	type Target = [Diagnostic];
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl IntoIterator for Diagnostics {
+	type IntoIter = std::vec::IntoIter<Diagnostic>;
+	type Item = Diagnostic;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.into_iter()
+	}
+}
+
+impl From<Diagnostic> for Diagnostics {
+	fn from(diagnostic: Diagnostic) -> Self {
+		Self(vec![diagnostic])
+	}
+}
+
+impl From<Vec<Diagnostic>> for Diagnostics {
+	fn from(diagnostics: Vec<Diagnostic>) -> Self {
+		Self(diagnostics)
+	}
+}
+
+impl fmt::Display for Diagnostics {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for (idx, diagnostic) in self.0.iter().enumerate() {
+			if idx > 0 {
+				writeln!(f)?;
+			}
+			write!(f, "{diagnostic}")?;
+		}
+
+		Ok(())
+	}
+}
+
+impl error::Error for Diagnostics {}
+
+impl From<Diagnostics> for io::Error {
+	fn from(diagnostics: Diagnostics) -> Self {
+		io::Error::other(diagnostics)
+	}
+}
@@ -0,0 +1,261 @@
+use std::{collections::BTreeMap, fmt::Write, rc::Rc};
+
+use crate::{
+	bytes::{DecodeError, FromBytes, Reader},
+	instruction::{Capture, Instruction},
+	token::TokenLine,
+	value::function::Function,
+	ValueType,
+};
+
+/// Turns bytecode back into readable text, one instruction per line:
+///
+/// ```text
+/// .const 2.5
+/// .string "b"
+/// 0000     1  PUSH_CONST 0                  ; 2.5
+/// 0005     |  DEFINE_GLOBAL "b"
+/// L0:
+/// 000a     2  LOAD_GLOBAL "b"
+/// 000f     |  JUMP_IF_FALSE L1
+/// ```
+///
+/// Every line starts with the instruction's byte offset, followed by its source line if it is known, `|` standing
+/// for the same line as the instruction before. Jump targets and function entries get a label, which operands
+/// refer to instead of the raw offset. The constant pool, string table and function table are listed first, one
+/// `.const`, `.string` or `.function` per entry. Constants and functions are also shown in a comment next to the
+/// instruction pushing them, names are written in place of their index.
+pub struct Disassembler<'a> {
+	code:      &'a [u8],
+	constants: &'a [ValueType],
+	strings:   &'a [String],
+	functions: &'a [Rc<Function>],
+	lines:     Vec<(usize, TokenLine)>,
+}
+
+struct Decoded {
+	offset: usize,
+	end:    usize,
+	instr:  Instruction,
+}
+
+impl<'a> Disassembler<'a> {
+	pub fn new(code: &'a [u8]) -> Self {
+		Self {
+			code,
+			constants: &[],
+			strings: &[],
+			functions: &[],
+			lines: Vec::new(),
+		}
+	}
+
+	pub fn withConstants(mut self, constants: &'a [ValueType]) -> Self {
+		self.constants = constants;
+		self
+	}
+
+	pub fn withStrings(mut self, strings: &'a [String]) -> Self {
+		self.strings = strings;
+		self
+	}
+
+	pub fn withFunctions(mut self, functions: &'a [Rc<Function>]) -> Self {
+		self.functions = functions;
+		self
+	}
+
+	/// Source line of the code starting at each byte offset, ordered by offset.
+	pub fn withLines(mut self, lines: Vec<(usize, TokenLine)>) -> Self {
+		self.lines = lines;
+		self
+	}
+
+	pub fn disassemble(&self) -> Result<String, DecodeError> {
+		let instrs = self.decode()?;
+		let labels = self.labels(&instrs);
+
+		let mut text = String::new();
+		let mut lastLine = None;
+
+		for value in self.constants.iter() {
+			writeln!(text, ".const {}", literal(value)).unwrap();
+		}
+		for name in self.strings.iter() {
+			writeln!(text, ".string {name:?}").unwrap();
+		}
+		for function in self.functions.iter() {
+			writeln!(text, ".function {}", signature(function, &labels)).unwrap();
+		}
+
+		for decoded in instrs.iter() {
+			if let Some(label) = labels.get(&decoded.offset) {
+				writeln!(text, "L{label}:").unwrap();
+			}
+
+			let mut line = format!("{:04x}", decoded.offset);
+
+			if !self.lines.is_empty() {
+				match self.line(decoded.offset) {
+					Some(l) if lastLine == Some(l) => line.push_str("     |"),
+					Some(l) => write!(line, "  {l:>4}").unwrap(),
+					None => line.push_str("      "),
+				}
+				lastLine = self.line(decoded.offset);
+			}
+
+			let instr = self.instruction(decoded, &labels);
+
+			match self.comment(&decoded.instr, &labels) {
+				Some(comment) => writeln!(text, "{line}  {instr:<30}; {comment}").unwrap(),
+				None => writeln!(text, "{line}  {instr}").unwrap(),
+			}
+		}
+
+		Ok(text)
+	}
+
+	fn decode(&self) -> Result<Vec<Decoded>, DecodeError> {
+		let mut reader = Reader::at(self.code, 0);
+		let mut instrs = Vec::new();
+
+		while reader.offset() < self.code.len() {
+			let offset = reader.offset();
+			let instr = Instruction::fromBytes(&mut reader)?;
+
+			instrs.push(Decoded {
+				offset,
+				end: reader.offset(),
+				instr,
+			});
+		}
+
+		Ok(instrs)
+	}
+
+	/// Number the offsets that are jumped to or that functions start at, in the order they appear in the code.
+	/// Targets in the middle of an instruction don't get a label, since there is nowhere to put it.
+	fn labels(&self, instrs: &[Decoded]) -> BTreeMap<usize, usize> {
+		let mut targets: Vec<usize> = instrs
+			.iter()
+			.filter_map(jumpTarget)
+			.chain(self.functions.iter().map(|function| function.entry().into()))
+			.filter_map(|target| usize::try_from(target).ok())
+			.filter(|target| instrs.binary_search_by_key(target, |decoded| decoded.offset).is_ok())
+			.collect();
+
+		targets.sort_unstable();
+		targets.dedup();
+
+		targets
+			.into_iter()
+			.enumerate()
+			.map(|(label, offset)| (offset, label))
+			.collect()
+	}
+
+	fn instruction(&self, decoded: &Decoded, labels: &BTreeMap<usize, usize>) -> String {
+		let mnemonic = decoded.instr.mnemonic();
+
+		if let Some(index) = decoded.instr.nameIndex() {
+			return match self.strings.get(index as usize) {
+				Some(name) => format!("{mnemonic} {name:?}"),
+				None => format!("{mnemonic} {index}"),
+			};
+		}
+
+		let operand = match &decoded.instr {
+			Instruction::Push(value) => literal(value),
+			Instruction::PushConst(index) | Instruction::PushFunction(index) => index.to_string(),
+			Instruction::LoadLocal(slot)
+			| Instruction::StoreLocal(slot)
+			| Instruction::LoadUpvalue(slot)
+			| Instruction::StoreUpvalue(slot) => slot.to_string(),
+			Instruction::Jump(_)
+			| Instruction::JumpIfFalse(_)
+			| Instruction::JumpIfTrue(_)
+			| Instruction::JumpIfFalseOrPop(_)
+			| Instruction::JumpIfTrueOrPop(_) => target(jumpTarget(decoded).unwrap(), labels),
+			Instruction::Call(argc) => argc.to_string(),
+			Instruction::Closure(captures) => captures
+				.iter()
+				.map(|Capture { isLocal, index }| {
+					if *isLocal {
+						format!("local {index}")
+					} else {
+						format!("upvalue {index}")
+					}
+				})
+				.collect::<Vec<_>>()
+				.join(", "),
+			_ => return mnemonic.to_string(),
+		};
+
+		format!("{mnemonic} {operand}")
+	}
+
+	fn comment(&self, instr: &Instruction, labels: &BTreeMap<usize, usize>) -> Option<String> {
+		match instr {
+			Instruction::PushConst(index) => Some(match self.constants.get(*index as usize) {
+				Some(value) => literal(value),
+				None => "no such constant".to_string(),
+			}),
+			Instruction::PushFunction(index) => Some(match self.functions.get(*index as usize) {
+				Some(function) => format!("fn {}", signature(function, labels)),
+				None => "no such function".to_string(),
+			}),
+			instr => {
+				let index = instr.nameIndex()?;
+				self.strings.get(index as usize).is_none().then(|| "no such string".to_string())
+			}
+		}
+	}
+
+	/// Source line of the instruction at `offset`, from the last entry at or before it.
+	fn line(&self, offset: usize) -> Option<TokenLine> {
+		let idx = self.lines.partition_point(|(start, _)| *start <= offset);
+		idx.checked_sub(1).map(|idx| self.lines[idx].1)
+	}
+}
+
+fn jumpTarget(decoded: &Decoded) -> Option<i64> {
+	match decoded.instr {
+		Instruction::Jump(offset)
+		| Instruction::JumpIfFalse(offset)
+		| Instruction::JumpIfTrue(offset)
+		| Instruction::JumpIfFalseOrPop(offset)
+		| Instruction::JumpIfTrueOrPop(offset) => Some(decoded.end as i64 + i64::from(offset)),
+		_ => None,
+	}
+}
+
+/// The label of `offset`, or the offset itself if it has none.
+fn target(offset: i64, labels: &BTreeMap<usize, usize>) -> String {
+	match usize::try_from(offset).ok().and_then(|offset| labels.get(&offset)) {
+		Some(label) => format!("L{label}"),
+		None => format!("@{offset}"),
+	}
+}
+
+/// A function as written in assembly: its name, arity and entry.
+fn signature(function: &Function, labels: &BTreeMap<usize, usize>) -> String {
+	format!(
+		"{}/{} {}",
+		function.name(),
+		function.arity(),
+		target(function.entry().into(), labels)
+	)
+}
+
+/// A value as written in assembly. The type of a number is kept with a suffix where the default wouldn't be it.
+fn literal(value: &ValueType) -> String {
+	match value {
+		ValueType::Int32(v) => format!("{v}i32"),
+		ValueType::Int64(v) => v.to_string(),
+		ValueType::Float32(v) => format!("{:?}f32", v.value()),
+		ValueType::Float64(v) => format!("{:?}", v.value()),
+		ValueType::Char(v) => format!("{:?}", v.value()),
+		ValueType::String(v) => format!("{:?}", v.asStr()),
+		other => other.to_string(),
+	}
+}
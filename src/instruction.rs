@@ -1,5 +1,5 @@
 use crate::{
-	bytes::{FromBytes, ToBytes},
+	bytes::{DecodeError, FromBytes, Reader, ToBytes},
 	ValueType,
 };
 
@@ -7,26 +7,243 @@ use crate::{
 pub(crate) const INSTR_HALT: u8 = 0x00;
 pub(crate) const INSTR_PUSH: u8 = 0x01;
 pub(crate) const INSTR_POP: u8 = 0x02;
+pub(crate) const INSTR_PUSH_CONST: u8 = 0x03;
+pub(crate) const INSTR_PUSH_FUNCTION: u8 = 0x04;
 
 pub(crate) const INSTR_ADD: u8 = 0x20;
 pub(crate) const INSTR_SUB: u8 = 0x21;
 pub(crate) const INSTR_MUL: u8 = 0x22;
 pub(crate) const INSTR_DIV: u8 = 0x23;
+pub(crate) const INSTR_NEG: u8 = 0x24;
+pub(crate) const INSTR_NOT: u8 = 0x25;
+pub(crate) const INSTR_BIT_NOT: u8 = 0x26;
+pub(crate) const INSTR_MOD: u8 = 0x27;
+pub(crate) const INSTR_POW: u8 = 0x28;
+pub(crate) const INSTR_BIT_AND: u8 = 0x29;
+pub(crate) const INSTR_BIT_OR: u8 = 0x2A;
+pub(crate) const INSTR_BIT_XOR: u8 = 0x2B;
+pub(crate) const INSTR_SHL: u8 = 0x2C;
+pub(crate) const INSTR_SHR: u8 = 0x2D;
 
 pub(crate) const INSTR_PRINT: u8 = 0x30;
+pub(crate) const INSTR_READ_LINE: u8 = 0x31;
+pub(crate) const INSTR_READ_CHAR: u8 = 0x32;
 
-pub(crate) type Instructions = Vec<u8>;
+pub(crate) const INSTR_LOAD_LOCAL: u8 = 0x40;
+pub(crate) const INSTR_STORE_LOCAL: u8 = 0x41;
+pub(crate) const INSTR_DEFINE_GLOBAL: u8 = 0x42;
+pub(crate) const INSTR_LOAD_GLOBAL: u8 = 0x43;
+pub(crate) const INSTR_STORE_GLOBAL: u8 = 0x44;
+pub(crate) const INSTR_LOAD_UPVALUE: u8 = 0x45;
+pub(crate) const INSTR_STORE_UPVALUE: u8 = 0x46;
+pub(crate) const INSTR_CLOSE_UPVALUE: u8 = 0x47;
+
+pub(crate) const INSTR_JUMP: u8 = 0x50;
+pub(crate) const INSTR_JUMP_IF_FALSE: u8 = 0x51;
+pub(crate) const INSTR_JUMP_IF_TRUE: u8 = 0x52;
+pub(crate) const INSTR_JUMP_IF_FALSE_OR_POP: u8 = 0x53;
+pub(crate) const INSTR_JUMP_IF_TRUE_OR_POP: u8 = 0x54;
+
+pub(crate) const INSTR_CALL: u8 = 0x60;
+pub(crate) const INSTR_RETURN: u8 = 0x61;
+pub(crate) const INSTR_CLOSURE: u8 = 0x62;
+
+pub(crate) const INSTR_CLASS: u8 = 0x70;
+pub(crate) const INSTR_INHERIT: u8 = 0x71;
+pub(crate) const INSTR_METHOD: u8 = 0x72;
+pub(crate) const INSTR_GET_PROPERTY: u8 = 0x73;
+pub(crate) const INSTR_SET_PROPERTY: u8 = 0x74;
+pub(crate) const INSTR_GET_SUPER: u8 = 0x75;
+
+pub(crate) const INSTR_EQUAL: u8 = 0x80;
+pub(crate) const INSTR_NOT_EQUAL: u8 = 0x81;
+pub(crate) const INSTR_LESS: u8 = 0x82;
+pub(crate) const INSTR_LESS_EQUAL: u8 = 0x83;
+pub(crate) const INSTR_GREATER: u8 = 0x84;
+pub(crate) const INSTR_GREATER_EQUAL: u8 = 0x85;
+
+pub type Instructions = Vec<u8>;
+
+/// Index of a local variable's slot on the stack.
+pub type Slot = u16;
+
+/// Index of a value in the program's constant pool.
+pub type ConstIndex = u32;
+
+/// Index of a name in the program's string table.
+pub type StringIndex = u32;
+
+/// Index of a function in the program's function table.
+pub type FunctionIndex = u32;
+
+/// Distance of a jump in bytes, counted from the end of the jump instruction.
+pub type Offset = i32;
+
+/// Encoded size of every jump instruction.
+pub(crate) const JUMP_SIZE: usize = 1 + std::mem::size_of::<Offset>();
+
+/// Where a closure finds a variable it captures.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capture {
+	/// `true` if `index` is a local slot of the enclosing function, `false` if it is one of the enclosing
+	/// function's own upvalues.
+	pub isLocal: bool,
+	pub index:   Slot,
+}
 
 #[derive(Debug)]
 pub enum Instruction {
 	Halt,
 	Push(ValueType),
 	Pop,
+	/// Push a copy of a value from the constant pool.
+	PushConst(ConstIndex),
+	/// Push a function from the function table.
+	PushFunction(FunctionIndex),
 	Add,
 	Sub,
 	Mul,
 	Div,
+	Neg,
+	/// Replace the top of the stack with `true` if it is falsy and `false` otherwise.
+	Not,
+	/// Bitwise complement of an int, logical not of a bool.
+	BitNot,
+	Mod,
+	Pow,
+	BitAnd,
+	BitOr,
+	BitXor,
+	Shl,
+	/// Arithmetic shift right.
+	Shr,
 	Print,
+	/// Read a line from the input, without its line ending, and push it as a string. Pushes null at the end.
+	ReadLine,
+	/// Read a single character from the input and push it. Pushes null at the end.
+	ReadChar,
+	/// Push a copy of the local in `Slot`.
+	LoadLocal(Slot),
+	/// Overwrite the local in `Slot` with the top of the stack, leaving the value on the stack.
+	StoreLocal(Slot),
+	/// Pop the top of the stack into a new global. Globals, classes, methods and properties are named by their
+	/// index in the string table.
+	DefineGlobal(StringIndex),
+	LoadGlobal(StringIndex),
+	/// Overwrite an existing global with the top of the stack, leaving the value on the stack.
+	StoreGlobal(StringIndex),
+	/// Push the value of the current closure's upvalue at the given index.
+	LoadUpvalue(Slot),
+	/// Overwrite an upvalue of the current closure with the top of the stack, leaving the value on the stack.
+	StoreUpvalue(Slot),
+	/// Move the captured local on top of the stack into its upvalue and pop it.
+	CloseUpvalue,
+	Jump(Offset),
+	/// Pop the top of the stack and jump if it is falsy.
+	JumpIfFalse(Offset),
+	/// Pop the top of the stack and jump if it is truthy.
+	JumpIfTrue(Offset),
+	/// Jump if the top of the stack is falsy, keeping it on the stack. Pop it otherwise.
+	JumpIfFalseOrPop(Offset),
+	/// Jump if the top of the stack is truthy, keeping it on the stack. Pop it otherwise.
+	JumpIfTrueOrPop(Offset),
+	/// Call the value below the given number of arguments on the stack.
+	Call(u8),
+	/// Return the top of the stack to the caller, discarding the current call frame.
+	Return,
+	/// Wrap the function on top of the stack in a closure capturing the given variables.
+	Closure(Vec<Capture>),
+	/// Push a new class without methods.
+	Class(StringIndex),
+	/// Copy the methods of the superclass below the class on top of the stack into it, popping the class.
+	Inherit,
+	/// Add the method on top of the stack to the class below it, popping the method.
+	Method(StringIndex),
+	/// Replace the instance on top of the stack with its field or bound method.
+	GetProperty(StringIndex),
+	/// Set a field of the instance below the top of the stack, leaving only the value on the stack.
+	SetProperty(StringIndex),
+	/// Replace the superclass and instance on top of the stack with the superclass' method bound to the instance.
+	GetSuper(StringIndex),
+	Equal,
+	NotEqual,
+	Less,
+	LessEqual,
+	Greater,
+	GreaterEqual,
+}
+
+impl Instruction {
+	/// Name of the instruction, as used in disassembly.
+	pub fn mnemonic(&self) -> &'static str {
+		match self {
+			Self::Halt => "HALT",
+			Self::Push(..) => "PUSH",
+			Self::Pop => "POP",
+			Self::PushConst(..) => "PUSH_CONST",
+			Self::PushFunction(..) => "PUSH_FUNCTION",
+			Self::Add => "ADD",
+			Self::Sub => "SUB",
+			Self::Mul => "MUL",
+			Self::Div => "DIV",
+			Self::Neg => "NEG",
+			Self::Not => "NOT",
+			Self::BitNot => "BIT_NOT",
+			Self::Mod => "MOD",
+			Self::Pow => "POW",
+			Self::BitAnd => "BIT_AND",
+			Self::BitOr => "BIT_OR",
+			Self::BitXor => "BIT_XOR",
+			Self::Shl => "SHL",
+			Self::Shr => "SHR",
+			Self::Print => "PRINT",
+			Self::ReadLine => "READ_LINE",
+			Self::ReadChar => "READ_CHAR",
+			Self::LoadLocal(..) => "LOAD_LOCAL",
+			Self::StoreLocal(..) => "STORE_LOCAL",
+			Self::DefineGlobal(..) => "DEFINE_GLOBAL",
+			Self::LoadGlobal(..) => "LOAD_GLOBAL",
+			Self::StoreGlobal(..) => "STORE_GLOBAL",
+			Self::LoadUpvalue(..) => "LOAD_UPVALUE",
+			Self::StoreUpvalue(..) => "STORE_UPVALUE",
+			Self::CloseUpvalue => "CLOSE_UPVALUE",
+			Self::Jump(..) => "JUMP",
+			Self::JumpIfFalse(..) => "JUMP_IF_FALSE",
+			Self::JumpIfTrue(..) => "JUMP_IF_TRUE",
+			Self::JumpIfFalseOrPop(..) => "JUMP_IF_FALSE_OR_POP",
+			Self::JumpIfTrueOrPop(..) => "JUMP_IF_TRUE_OR_POP",
+			Self::Call(..) => "CALL",
+			Self::Return => "RETURN",
+			Self::Closure(..) => "CLOSURE",
+			Self::Class(..) => "CLASS",
+			Self::Inherit => "INHERIT",
+			Self::Method(..) => "METHOD",
+			Self::GetProperty(..) => "GET_PROPERTY",
+			Self::SetProperty(..) => "SET_PROPERTY",
+			Self::GetSuper(..) => "GET_SUPER",
+			Self::Equal => "EQUAL",
+			Self::NotEqual => "NOT_EQUAL",
+			Self::Less => "LESS",
+			Self::LessEqual => "LESS_EQUAL",
+			Self::Greater => "GREATER",
+			Self::GreaterEqual => "GREATER_EQUAL",
+		}
+	}
+
+	/// Index into the string table of the name the instruction refers to, if it refers to one.
+	pub fn nameIndex(&self) -> Option<StringIndex> {
+		match self {
+			Self::DefineGlobal(index)
+			| Self::LoadGlobal(index)
+			| Self::StoreGlobal(index)
+			| Self::Class(index)
+			| Self::Method(index)
+			| Self::GetProperty(index)
+			| Self::SetProperty(index)
+			| Self::GetSuper(index) => Some(*index),
+			_ => None,
+		}
+	}
 }
 
 impl ToBytes for Instruction {
@@ -40,13 +257,117 @@ impl ToBytes for Instruction {
 				bytes.extend(v.bytes());
 			}
 			Self::Pop => bytes.push(INSTR_POP),
+			Self::PushConst(index) => {
+				bytes.push(INSTR_PUSH_CONST);
+				bytes.extend(index.to_le_bytes());
+			}
+			Self::PushFunction(index) => {
+				bytes.push(INSTR_PUSH_FUNCTION);
+				bytes.extend(index.to_le_bytes());
+			}
 			Self::Add => bytes.push(INSTR_ADD),
 			Self::Sub => bytes.push(INSTR_SUB),
 			Self::Mul => bytes.push(INSTR_MUL),
 			Self::Div => bytes.push(INSTR_DIV),
+			Self::Neg => bytes.push(INSTR_NEG),
+			Self::Not => bytes.push(INSTR_NOT),
+			Self::BitNot => bytes.push(INSTR_BIT_NOT),
+			Self::Mod => bytes.push(INSTR_MOD),
+			Self::Pow => bytes.push(INSTR_POW),
+			Self::BitAnd => bytes.push(INSTR_BIT_AND),
+			Self::BitOr => bytes.push(INSTR_BIT_OR),
+			Self::BitXor => bytes.push(INSTR_BIT_XOR),
+			Self::Shl => bytes.push(INSTR_SHL),
+			Self::Shr => bytes.push(INSTR_SHR),
 			Self::Print => bytes.push(INSTR_PRINT),
-			#[allow(unreachable_patterns)]
-			other => panic!("not implemented {other:?}.toBytes()"),
+			Self::ReadLine => bytes.push(INSTR_READ_LINE),
+			Self::ReadChar => bytes.push(INSTR_READ_CHAR),
+			Self::LoadLocal(slot) => {
+				bytes.push(INSTR_LOAD_LOCAL);
+				bytes.extend(slot.to_le_bytes());
+			}
+			Self::StoreLocal(slot) => {
+				bytes.push(INSTR_STORE_LOCAL);
+				bytes.extend(slot.to_le_bytes());
+			}
+			Self::DefineGlobal(name) => {
+				bytes.push(INSTR_DEFINE_GLOBAL);
+				bytes.extend(name.to_le_bytes());
+			}
+			Self::LoadGlobal(name) => {
+				bytes.push(INSTR_LOAD_GLOBAL);
+				bytes.extend(name.to_le_bytes());
+			}
+			Self::StoreGlobal(name) => {
+				bytes.push(INSTR_STORE_GLOBAL);
+				bytes.extend(name.to_le_bytes());
+			}
+			Self::LoadUpvalue(idx) => {
+				bytes.push(INSTR_LOAD_UPVALUE);
+				bytes.extend(idx.to_le_bytes());
+			}
+			Self::StoreUpvalue(idx) => {
+				bytes.push(INSTR_STORE_UPVALUE);
+				bytes.extend(idx.to_le_bytes());
+			}
+			Self::CloseUpvalue => bytes.push(INSTR_CLOSE_UPVALUE),
+			Self::Jump(offset) => {
+				bytes.push(INSTR_JUMP);
+				bytes.extend(offset.to_le_bytes());
+			}
+			Self::JumpIfFalse(offset) => {
+				bytes.push(INSTR_JUMP_IF_FALSE);
+				bytes.extend(offset.to_le_bytes());
+			}
+			Self::JumpIfTrue(offset) => {
+				bytes.push(INSTR_JUMP_IF_TRUE);
+				bytes.extend(offset.to_le_bytes());
+			}
+			Self::JumpIfFalseOrPop(offset) => {
+				bytes.push(INSTR_JUMP_IF_FALSE_OR_POP);
+				bytes.extend(offset.to_le_bytes());
+			}
+			Self::JumpIfTrueOrPop(offset) => {
+				bytes.push(INSTR_JUMP_IF_TRUE_OR_POP);
+				bytes.extend(offset.to_le_bytes());
+			}
+			Self::Call(argc) => bytes.extend([INSTR_CALL, *argc]),
+			Self::Return => bytes.push(INSTR_RETURN),
+			Self::Closure(captures) => {
+				bytes.push(INSTR_CLOSURE);
+				bytes.extend((captures.len() as u16).to_le_bytes());
+				for capture in captures {
+					bytes.push(capture.isLocal.into());
+					bytes.extend(capture.index.to_le_bytes());
+				}
+			}
+			Self::Class(name) => {
+				bytes.push(INSTR_CLASS);
+				bytes.extend(name.to_le_bytes());
+			}
+			Self::Inherit => bytes.push(INSTR_INHERIT),
+			Self::Method(name) => {
+				bytes.push(INSTR_METHOD);
+				bytes.extend(name.to_le_bytes());
+			}
+			Self::GetProperty(name) => {
+				bytes.push(INSTR_GET_PROPERTY);
+				bytes.extend(name.to_le_bytes());
+			}
+			Self::SetProperty(name) => {
+				bytes.push(INSTR_SET_PROPERTY);
+				bytes.extend(name.to_le_bytes());
+			}
+			Self::GetSuper(name) => {
+				bytes.push(INSTR_GET_SUPER);
+				bytes.extend(name.to_le_bytes());
+			}
+			Self::Equal => bytes.push(INSTR_EQUAL),
+			Self::NotEqual => bytes.push(INSTR_NOT_EQUAL),
+			Self::Less => bytes.push(INSTR_LESS),
+			Self::LessEqual => bytes.push(INSTR_LESS_EQUAL),
+			Self::Greater => bytes.push(INSTR_GREATER),
+			Self::GreaterEqual => bytes.push(INSTR_GREATER_EQUAL),
 		}
 
 		bytes
@@ -54,22 +375,87 @@ impl ToBytes for Instruction {
 }
 
 impl FromBytes for Instruction {
-	fn fromBytes(bytes: &mut Vec<u8>) -> Self {
-		match bytes.remove(0) {
+	fn fromBytes(bytes: &mut Reader) -> Result<Self, DecodeError> {
+		Ok(match bytes.takeByte()? {
 			INSTR_HALT => Self::Halt,
 			INSTR_PUSH => {
-				let value = ValueType::fromBytes(bytes);
+				let value = ValueType::fromBytes(bytes)?;
 
 				Self::Push(value)
 			}
 			INSTR_POP => Self::Pop,
+			INSTR_PUSH_CONST => Self::PushConst(ConstIndex::from_le_bytes(bytes.takeArray()?)),
+			INSTR_PUSH_FUNCTION => Self::PushFunction(FunctionIndex::from_le_bytes(bytes.takeArray()?)),
 			INSTR_ADD => Self::Add,
 			INSTR_SUB => Self::Sub,
 			INSTR_MUL => Self::Mul,
 			INSTR_DIV => Self::Div,
+			INSTR_NEG => Self::Neg,
+			INSTR_NOT => Self::Not,
+			INSTR_BIT_NOT => Self::BitNot,
+			INSTR_MOD => Self::Mod,
+			INSTR_POW => Self::Pow,
+			INSTR_BIT_AND => Self::BitAnd,
+			INSTR_BIT_OR => Self::BitOr,
+			INSTR_BIT_XOR => Self::BitXor,
+			INSTR_SHL => Self::Shl,
+			INSTR_SHR => Self::Shr,
 			INSTR_PRINT => Self::Print,
-			#[allow(unreachable_patterns)]
-			other => panic!("invalid instruction: {other:x}"),
-		}
+			INSTR_READ_LINE => Self::ReadLine,
+			INSTR_READ_CHAR => Self::ReadChar,
+			INSTR_LOAD_LOCAL => Self::LoadLocal(slotFromBytes(bytes)?),
+			INSTR_STORE_LOCAL => Self::StoreLocal(slotFromBytes(bytes)?),
+			INSTR_DEFINE_GLOBAL => Self::DefineGlobal(stringFromBytes(bytes)?),
+			INSTR_LOAD_GLOBAL => Self::LoadGlobal(stringFromBytes(bytes)?),
+			INSTR_STORE_GLOBAL => Self::StoreGlobal(stringFromBytes(bytes)?),
+			INSTR_LOAD_UPVALUE => Self::LoadUpvalue(slotFromBytes(bytes)?),
+			INSTR_STORE_UPVALUE => Self::StoreUpvalue(slotFromBytes(bytes)?),
+			INSTR_CLOSE_UPVALUE => Self::CloseUpvalue,
+			INSTR_JUMP => Self::Jump(offsetFromBytes(bytes)?),
+			INSTR_JUMP_IF_FALSE => Self::JumpIfFalse(offsetFromBytes(bytes)?),
+			INSTR_JUMP_IF_TRUE => Self::JumpIfTrue(offsetFromBytes(bytes)?),
+			INSTR_JUMP_IF_FALSE_OR_POP => Self::JumpIfFalseOrPop(offsetFromBytes(bytes)?),
+			INSTR_JUMP_IF_TRUE_OR_POP => Self::JumpIfTrueOrPop(offsetFromBytes(bytes)?),
+			INSTR_CALL => Self::Call(bytes.takeByte()?),
+			INSTR_RETURN => Self::Return,
+			INSTR_CLOSURE => {
+				let count = u16::from_le_bytes(bytes.takeArray()?);
+				let captures = (0..count)
+					.map(|_| {
+						Ok(Capture {
+							isLocal: bytes.takeByte()? != 0,
+							index:   slotFromBytes(bytes)?,
+						})
+					})
+					.collect::<Result<_, DecodeError>>()?;
+
+				Self::Closure(captures)
+			}
+			INSTR_CLASS => Self::Class(stringFromBytes(bytes)?),
+			INSTR_INHERIT => Self::Inherit,
+			INSTR_METHOD => Self::Method(stringFromBytes(bytes)?),
+			INSTR_GET_PROPERTY => Self::GetProperty(stringFromBytes(bytes)?),
+			INSTR_SET_PROPERTY => Self::SetProperty(stringFromBytes(bytes)?),
+			INSTR_GET_SUPER => Self::GetSuper(stringFromBytes(bytes)?),
+			INSTR_EQUAL => Self::Equal,
+			INSTR_NOT_EQUAL => Self::NotEqual,
+			INSTR_LESS => Self::Less,
+			INSTR_LESS_EQUAL => Self::LessEqual,
+			INSTR_GREATER => Self::Greater,
+			INSTR_GREATER_EQUAL => Self::GreaterEqual,
+			other => return Err(bytes.unknownTag("instruction", other)),
+		})
 	}
 }
+
+fn slotFromBytes(bytes: &mut Reader) -> Result<Slot, DecodeError> {
+	Ok(Slot::from_le_bytes(bytes.takeArray()?))
+}
+
+fn stringFromBytes(bytes: &mut Reader) -> Result<StringIndex, DecodeError> {
+	Ok(StringIndex::from_le_bytes(bytes.takeArray()?))
+}
+
+fn offsetFromBytes(bytes: &mut Reader) -> Result<Offset, DecodeError> {
+	Ok(Offset::from_le_bytes(bytes.takeArray()?))
+}
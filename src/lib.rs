@@ -1,13 +1,24 @@
 #![allow(non_snake_case)]
+#![allow(clippy::result_large_err)]
 
 use value::Value2;
 
+pub mod assembler;
 pub mod ast;
 pub mod bytes;
-mod instruction;
+pub mod compiler;
+pub mod container;
+pub mod debuginfo;
+pub mod diagnostic;
+pub mod disassembler;
+pub mod instruction;
+pub mod parser;
+pub mod runtime;
+pub mod span;
 pub mod token;
 pub mod tokenizer;
-mod value;
+pub mod value;
+pub mod verifier;
 pub mod vm;
 
 #[macro_export]
@@ -30,6 +41,6 @@ macro_rules! Err {
 	};
 }
 
-pub(crate) type ValueType = Value2;
+pub type ValueType = Value2;
 
 pub const MAGIC_NUMBER: [u8; 3] = [0x6e, 0x79, 0x62];
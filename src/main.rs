@@ -0,0 +1,168 @@
+#![allow(non_snake_case)]
+
+use std::{env, fs, path::Path, process};
+
+use nyooom_vm::{ast::AST, bytes::ToBytes, container::Container, parser::Parser, vm::VM, MAGIC_NUMBER};
+
+const USAGE: &str = "usage: nyooom <command> <file>
+
+commands:
+  run <file>            run a source or bytecode file, exiting with the program's exit code
+  compile <file>        compile a source file to bytecode
+    -o <output>         where to write the bytecode, the source file with a .nyb extension by default
+    --strip             leave out the debug info
+  disassemble <file>    print the bytecode of a source or bytecode file
+  check <file>          report errors in a source or bytecode file without running it
+";
+
+// Exit codes
+const EXIT_OK: i32 = 0;
+const EXIT_ERROR: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+enum Failure {
+	/// The command line doesn't make sense, the usage is printed after the message.
+	Usage(String),
+	/// Already formatted errors, printed as they are.
+	Error(String),
+}
+
+type Result<T> = std::result::Result<T, Failure>;
+
+fn main() {
+	let args: Vec<String> = env::args().skip(1).collect();
+
+	let code = match execute(&args) {
+		Ok(code) => code,
+		Err(Failure::Usage(msg)) => {
+			eprint!("error: {msg}\n\n{USAGE}");
+			EXIT_USAGE
+		}
+		Err(Failure::Error(msg)) => {
+			eprint!("{msg}");
+			EXIT_ERROR
+		}
+	};
+
+	process::exit(code);
+}
+
+fn execute(args: &[String]) -> Result<i32> {
+	let Some((command, args)) = args.split_first() else {
+		return Err(Failure::Usage("no command given".to_string()));
+	};
+
+	match command.as_str() {
+		"run" => run(file(args)?),
+		"compile" => compile(args),
+		"disassemble" => disassemble(file(args)?),
+		"check" => load(file(args)?).map(|_| EXIT_OK),
+		"help" | "-h" | "--help" => {
+			print!("{USAGE}");
+			Ok(EXIT_OK)
+		}
+		other => Err(Failure::Usage(format!("unknown command '{other}'"))),
+	}
+}
+
+/// The only argument of a command that takes just a file.
+fn file(args: &[String]) -> Result<&str> {
+	match args {
+		[file] => Ok(file),
+		[] => Err(Failure::Usage("no file given".to_string())),
+		[_, extra, ..] => Err(Failure::Usage(format!("unexpected argument '{extra}'"))),
+	}
+}
+
+fn run(path: &str) -> Result<i32> {
+	let mut vm = load(path)?;
+
+	vm.run().map_err(|e| Failure::Error(e.traceback()))
+}
+
+fn compile(args: &[String]) -> Result<i32> {
+	let mut path = None;
+	let mut output = None;
+	let mut strip = false;
+
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"-o" => match args.next() {
+				Some(out) => output = Some(out.as_str()),
+				None => return Err(Failure::Usage("expected a file after '-o'".to_string())),
+			},
+			"--strip" => strip = true,
+			_ if path.is_none() => path = Some(arg.as_str()),
+			_ => return Err(Failure::Usage(format!("unexpected argument '{arg}'"))),
+		}
+	}
+
+	let Some(path) = path else {
+		return Err(Failure::Usage("no file given".to_string()));
+	};
+
+	let source = source(path, &read(path)?)?;
+	let mut container = Container::try_from(parse(&source, path)?).map_err(|e| Failure::Error(e.render(&source, path)))?;
+
+	if strip {
+		container.stripDebugInfo();
+	} else if let Some(debugInfo) = container.debugInfo() {
+		let debugInfo = debugInfo.clone().withFile(path);
+		container = container.withDebugInfo(debugInfo);
+	}
+
+	let output = match output {
+		Some(output) => output.into(),
+		None => Path::new(path).with_extension("nyb"),
+	};
+
+	fs::write(&output, container.bytes())
+		.map_err(|e| Failure::Error(format!("error: cannot write {}: {e}\n", output.display())))?;
+
+	Ok(EXIT_OK)
+}
+
+fn disassemble(path: &str) -> Result<i32> {
+	let vm = load(path)?;
+	let text = vm.disassemble().map_err(|e| Failure::Error(format!("error: {e}\n")))?;
+
+	print!("{text}");
+	Ok(EXIT_OK)
+}
+
+/// Load a bytecode file, recognized by its magic number, or compile a source file. Either way, the program is
+/// verified before it is returned.
+fn load(path: &str) -> Result<VM> {
+	let bytes = read(path)?;
+
+	let vm = if bytes.starts_with(&MAGIC_NUMBER) {
+		let container = Container::read(&bytes).map_err(|e| Failure::Error(format!("error: {path}: {e}\n")))?;
+		VM::from(container)
+	} else {
+		let source = source(path, &bytes)?;
+		let ast = parse(&source, path)?;
+		VM::try_from(ast)
+			.map_err(|e| Failure::Error(e.render(&source, path)))?
+			.withFile(path)
+	};
+
+	match vm.verify() {
+		Ok(()) => Ok(vm),
+		Err(problems) => Err(Failure::Error(
+			problems.iter().map(|problem| format!("error: {path}: {problem}\n")).collect(),
+		)),
+	}
+}
+
+fn read(path: &str) -> Result<Vec<u8>> {
+	fs::read(path).map_err(|e| Failure::Error(format!("error: cannot read {path}: {e}\n")))
+}
+
+fn source(path: &str, bytes: &[u8]) -> Result<String> {
+	String::from_utf8(bytes.to_vec()).map_err(|_| Failure::Error(format!("error: {path} is not valid UTF-8\n")))
+}
+
+fn parse(source: &str, path: &str) -> Result<AST> {
+	Parser::parse(source).map_err(|e| Failure::Error(e.render(source, path)))
+}
@@ -1,18 +1,744 @@
-use std::io::Result;
+use crate::{
+	ast::{
+		literal::Literal,
+		node::{Node, NodeKind},
+		GenerateAST,
+		Tokens,
+		AST,
+	},
+	diagnostic::{Diagnostic, Diagnostics, ERR_INVALID_ASSIGNMENT, ERR_INVALID_LITERAL, ERR_SYNTAX, ERR_UNEXPECTED_TOKEN},
+	span::Span,
+	token::{keyword::Keyword, symbol::Symbol, tokenkind::TokenKind, Token, TokenValue},
+	tokenizer::Tokenizer,
+	value::Value2,
+};
 
-use crate::parser::tokenizer::Tokenizer;
+type Result<T> = std::result::Result<T, Diagnostic>;
 
-mod keyword;
-pub(crate) mod token;
-mod tokenizer;
-mod tokenkind;
-
-pub(crate) struct Parser {}
+/// Recursive-descent parser turning a token stream into a `NodeKind::Program`.
+///
+/// Operator precedence, from loosest to tightest binding:
+///
+/// | operators           | associativity |
+/// |---------------------|---------------|
+/// | `=`                 | right         |
+/// | `\|\|`              | left          |
+/// | `&&`                | left          |
+/// | `==` `!=`           | left          |
+/// | `<` `<=` `>` `>=`   | left          |
+/// | `\|`                | left          |
+/// | `^`                 | left          |
+/// | `&`                 | left          |
+/// | `<<` `>>`           | left          |
+/// | `+` `-`             | left          |
+/// | `*` `/` `%`         | left          |
+/// | `-` `!` `~` (unary) | right         |
+/// | `**`                | right         |
+/// | call, `.`, `[]`     | left          |
+///
+/// Computed member access `a[b]` is parsed, but the compiler rejects it as not supported yet.
+///
+/// Syntax errors don't stop the parser: the offending declaration is replaced by a `NodeKind::Error` placeholder,
+/// the parser skips ahead to the next statement boundary and carries on, so every error in a file is reported in
+/// one pass.
+pub struct Parser {
+	tokens:  Tokens,
+	current: usize,
+	errors:  Diagnostics,
+}
 
 impl Parser {
-	#[allow(unreachable_code)]
-	pub(crate) fn parse(text: String) -> Result<AST> {
-		let tokens = Tokenizer::new().tokenize(text.as_bytes().to_vec().iter().peekable())?;
-		todo!("parse")
+	/// Tokenize and parse `text` into an `AST`, failing with every syntax error found.
+	pub fn parse(text: &str) -> std::result::Result<AST, Diagnostics> {
+		let (ast, errors) = Self::parseRecovering(text);
+
+		if errors.hasErrors() {
+			Err(errors)
+		} else {
+			Ok(ast)
+		}
+	}
+
+	/// Tokenize and parse `text`, returning the (possibly partial) `AST` together with every syntax error found.
+	/// Unparsable declarations show up as `NodeKind::Error` nodes in the tree.
+	pub fn parseRecovering(text: &str) -> (AST, Diagnostics) {
+		let (tokens, mut errors) = Tokenizer::tokenizeRecovering(text.as_bytes().iter().peekable());
+
+		let mut parser = Self::new(tokens);
+		let program = parser.program();
+		errors.extend(parser.errors);
+
+		(AST::new(program), errors)
+	}
+
+	fn new(mut tokens: Tokens) -> Self {
+		if !tokens.last().is_some_and(|tk| tk.kind() == TokenKind::EOF) {
+			let end = tokens
+				.last()
+				.map_or(Span::default(), |tk| Span::new(tk.span().end(), tk.span().end()));
+			tokens.push(Token::new(TokenKind::EOF, TokenValue::None, end));
+		}
+
+		Self {
+			tokens,
+			current: 0,
+			errors: Diagnostics::new(),
+		}
+	}
+
+	fn program(&mut self) -> Node {
+		let start = self.peek().span();
+		let mut body = Vec::new();
+
+		while !self.isAtEnd() {
+			body.push(self.declaration());
+		}
+
+		Node::new(NodeKind::Program(body), start.to(self.peek().span()))
+	}
+
+	/// Parse a declaration, recovering from syntax errors by recording them and skipping to the next statement.
+	fn declaration(&mut self) -> Node {
+		let from = self.current;
+		let start = self.peek().span();
+
+		match self.tryDeclaration() {
+			Ok(node) => node,
+			Err(e) => {
+				// invalid tokens have already been reported by the tokenizer
+				if !matches!(self.peek().kind(), TokenKind::Err(_)) {
+					self.errors.push(e);
+				}
+				self.synchronize(from);
+				self.finish(start, NodeKind::Error)
+			}
+		}
+	}
+
+	/// Skip tokens until just after a `;`, or until the next `}` or statement keyword.
+	fn synchronize(&mut self, from: usize) {
+		// always make progress, otherwise a token that can't start a declaration would be retried forever
+		if self.current == from {
+			self.advance();
+		}
+
+		while !self.isAtEnd() {
+			if self.previous().kind() == TokenKind::Symbol(Symbol::Semicolon) || self.checkSymbol(Symbol::RBrace) {
+				return;
+			}
+
+			match self.peekKeyword() {
+				Some(
+					Keyword::Class
+					| Keyword::Def
+					| Keyword::Let
+					| Keyword::If
+					| Keyword::While
+					| Keyword::Do
+					| Keyword::For
+					| Keyword::Return
+					| Keyword::Print,
+				) => return,
+				_ => self.advance(),
+			};
+		}
+	}
+
+	fn tryDeclaration(&mut self) -> Result<Node> {
+		match self.peekKeyword() {
+			Some(Keyword::Class) => self.classDeclaration(),
+			Some(Keyword::Def) => self.functionDeclaration(),
+			Some(Keyword::Let) => self.variableStatement(),
+			_ => self.statement(),
+		}
+	}
+
+	/// `class NAME (: SUPERCLASS)? { def ... }`
+	fn classDeclaration(&mut self) -> Result<Node> {
+		let start = self.advance().span();
+
+		let name = self.identifier("class name")?;
+		let superclass = if self.matchSymbol(Symbol::Colon) {
+			Some(Box::new(self.identifier("superclass name")?))
+		} else {
+			None
+		};
+
+		let bodyStart = self.expect(Symbol::LBrace, "'{' before class body")?.span();
+
+		let mut methods = Vec::new();
+		while !self.checkSymbol(Symbol::RBrace) && !self.isAtEnd() {
+			if self.peekKeyword() == Some(Keyword::Def) {
+				methods.push(self.declaration());
+				continue;
+			}
+
+			// skip the whole member, up to the next method or the end of the class body
+			let memberStart = self.peek().span();
+			if let Err(e) = self.error::<()>("expected method declaration in class body") {
+				self.errors.push(e);
+			}
+
+			let mut depth = 0usize;
+			while !self.isAtEnd()
+				&& !(depth == 0 && (self.checkSymbol(Symbol::RBrace) || self.peekKeyword() == Some(Keyword::Def)))
+			{
+				match self.advance().kind() {
+					TokenKind::Symbol(Symbol::LBrace) => depth += 1,
+					TokenKind::Symbol(Symbol::RBrace) => depth -= 1,
+					_ => {}
+				}
+			}
+			methods.push(self.finish(memberStart, NodeKind::Error));
+		}
+
+		self.expect(Symbol::RBrace, "'}' after class body")?;
+		let body = self.finish(bodyStart, NodeKind::Block(methods));
+
+		Ok(self.finish(start, NodeKind::Class(Box::new(name), superclass, Box::new(body))))
+	}
+
+	/// `def NAME(PARAMS) { BODY }`
+	fn functionDeclaration(&mut self) -> Result<Node> {
+		let start = self.advance().span();
+
+		let name = self.identifier("function name")?;
+		self.expect(Symbol::LParen, "'(' after function name")?;
+
+		let mut params = Vec::new();
+		if !self.checkSymbol(Symbol::RParen) {
+			loop {
+				params.push(self.identifier("parameter name")?);
+				if !self.matchSymbol(Symbol::Comma) {
+					break;
+				}
+			}
+		}
+
+		self.expect(Symbol::RParen, "')' after parameters")?;
+
+		if !self.checkSymbol(Symbol::LBrace) {
+			return self.error("expected '{' before function body");
+		}
+		let body = self.block()?;
+
+		Ok(self.finish(start, NodeKind::Function(Box::new(name), params, Box::new(body))))
+	}
+
+	/// `let NAME (= INIT)? (, NAME (= INIT)?)* ;`
+	fn variableStatement(&mut self) -> Result<Node> {
+		let start = self.advance().span();
+
+		let mut declarations = Vec::new();
+		loop {
+			let name = self.identifier("variable name")?;
+			let init = if self.matchSymbol(Symbol::Equal) {
+				self.expression()?
+			} else {
+				Node::new(NodeKind::Literal(Literal::new(Value2::Null)), name.span())
+			};
+
+			let span = name.span().to(init.span());
+			declarations.push(Node::new(NodeKind::VariableDeclaration(Box::new(name), Box::new(init)), span));
+
+			if !self.matchSymbol(Symbol::Comma) {
+				break;
+			}
+		}
+
+		self.expect(Symbol::Semicolon, "';' after variable declaration")?;
+
+		Ok(self.finish(start, NodeKind::VariableStatement(declarations)))
+	}
+
+	fn statement(&mut self) -> Result<Node> {
+		match self.peekKeyword() {
+			Some(Keyword::If) => self.ifStatement(),
+			Some(Keyword::While) => self.whileStatement(),
+			Some(Keyword::Do) => self.doWhileStatement(),
+			Some(Keyword::For) => self.forStatement(),
+			Some(Keyword::Return) => self.returnStatement(),
+			Some(Keyword::Print) => self.printStatement(),
+			_ if self.checkSymbol(Symbol::LBrace) => self.block(),
+			_ if self.matchSymbol(Symbol::Semicolon) => Ok(Node::new(NodeKind::EmptyStatement, self.previous().span())),
+			_ => self.expressionStatement(),
+		}
+	}
+
+	fn block(&mut self) -> Result<Node> {
+		let start = self.expect(Symbol::LBrace, "'{'")?.span();
+
+		let mut body = Vec::new();
+		while !self.checkSymbol(Symbol::RBrace) && !self.isAtEnd() {
+			body.push(self.declaration());
+		}
+
+		self.expect(Symbol::RBrace, "'}' after block")?;
+
+		Ok(self.finish(start, NodeKind::Block(body)))
+	}
+
+	/// `if (TEST) CONSEQUENT (else ALTERNATE)?`
+	fn ifStatement(&mut self) -> Result<Node> {
+		let start = self.advance().span();
+
+		self.expect(Symbol::LParen, "'(' after 'if'")?;
+		let test = self.expression()?;
+		self.expect(Symbol::RParen, "')' after if condition")?;
+
+		let consequent = self.statement()?;
+		let alternate = if self.matchKeyword(Keyword::Else) {
+			Some(Box::new(self.statement()?))
+		} else {
+			None
+		};
+
+		Ok(self.finish(start, NodeKind::IfStatement(Box::new(test), Box::new(consequent), alternate)))
+	}
+
+	/// `while (TEST) BODY`
+	fn whileStatement(&mut self) -> Result<Node> {
+		let start = self.advance().span();
+
+		self.expect(Symbol::LParen, "'(' after 'while'")?;
+		let test = self.expression()?;
+		self.expect(Symbol::RParen, "')' after while condition")?;
+
+		let body = self.statement()?;
+
+		Ok(self.finish(start, NodeKind::WhileStatement(Box::new(test), Box::new(body))))
+	}
+
+	/// `do BODY while (TEST);`
+	fn doWhileStatement(&mut self) -> Result<Node> {
+		let start = self.advance().span();
+
+		let body = self.statement()?;
+
+		if !self.matchKeyword(Keyword::While) {
+			return self.error("expected 'while' after do body");
+		}
+		self.expect(Symbol::LParen, "'(' after 'while'")?;
+		let test = self.expression()?;
+		self.expect(Symbol::RParen, "')' after while condition")?;
+		self.expect(Symbol::Semicolon, "';' after do-while statement")?;
+
+		Ok(self.finish(start, NodeKind::DoWhileStatement(Box::new(body), Box::new(test))))
+	}
+
+	/// `for (INIT? ; TEST? ; UPDATE?) BODY`
+	fn forStatement(&mut self) -> Result<Node> {
+		let start = self.advance().span();
+
+		self.expect(Symbol::LParen, "'(' after 'for'")?;
+
+		let init = if self.matchSymbol(Symbol::Semicolon) {
+			None
+		} else if self.peekKeyword() == Some(Keyword::Let) {
+			Some(Box::new(self.variableStatement()?))
+		} else {
+			Some(Box::new(self.expressionStatement()?))
+		};
+
+		let test = if self.checkSymbol(Symbol::Semicolon) {
+			None
+		} else {
+			Some(Box::new(self.expression()?))
+		};
+		self.expect(Symbol::Semicolon, "';' after loop condition")?;
+
+		let update = if self.checkSymbol(Symbol::RParen) {
+			None
+		} else {
+			Some(Box::new(self.expression()?))
+		};
+		self.expect(Symbol::RParen, "')' after for clauses")?;
+
+		let body = self.statement()?;
+
+		Ok(self.finish(start, NodeKind::ForStatement(init, test, update, Box::new(body))))
+	}
+
+	/// `return VALUE?;`
+	fn returnStatement(&mut self) -> Result<Node> {
+		let start = self.advance().span();
+
+		let value = if self.checkSymbol(Symbol::Semicolon) {
+			None
+		} else {
+			Some(Box::new(self.expression()?))
+		};
+		self.expect(Symbol::Semicolon, "';' after return value")?;
+
+		Ok(self.finish(start, NodeKind::ReturnStatement(value)))
+	}
+
+	/// `print VALUE;`
+	fn printStatement(&mut self) -> Result<Node> {
+		let start = self.advance().span();
+
+		let value = self.expression()?;
+		self.expect(Symbol::Semicolon, "';' after print value")?;
+
+		Ok(self.finish(start, NodeKind::PrintExpression(Box::new(value))))
+	}
+
+	fn expressionStatement(&mut self) -> Result<Node> {
+		let expr = self.expression()?;
+		self.expect(Symbol::Semicolon, "';' after expression")?;
+
+		Ok(self.finish(expr.span(), NodeKind::ExpressionStatement(Box::new(expr))))
+	}
+
+	fn expression(&mut self) -> Result<Node> {
+		self.assignment()
+	}
+
+	/// Assignments are `BinaryExpression`s with an `=` operator whose left side is an
+	/// `Identifier` or `MemberExpression`.
+	fn assignment(&mut self) -> Result<Node> {
+		let target = self.logicalOr()?;
+
+		if !self.checkSymbol(Symbol::Equal) {
+			return Ok(target);
+		}
+
+		let op = self.advance();
+		let value = self.assignment()?;
+
+		match target.kind() {
+			NodeKind::Identifier(_) | NodeKind::MemberExpression(_, _, _) => {
+				let span = target.span().to(value.span());
+				Ok(Node::new(
+					NodeKind::BinaryExpression(op, Box::new(target), Box::new(value)),
+					span,
+				))
+			}
+			_ => Err(Diagnostic::error(ERR_INVALID_ASSIGNMENT, "invalid assignment target")
+				.withPrimary(target.span(), "cannot assign to this expression")
+				.withSecondary(op.span(), "assignment happens here")
+				.withHelp("only variables and properties can be assigned to")),
+		}
+	}
+
+	fn logicalOr(&mut self) -> Result<Node> {
+		self.binary(&[TokenKind::Compound(Symbol::Pipe, Symbol::Pipe)], Self::logicalAnd)
+	}
+
+	fn logicalAnd(&mut self) -> Result<Node> {
+		self.binary(&[TokenKind::Compound(Symbol::Ampersand, Symbol::Ampersand)], Self::equality)
+	}
+
+	fn equality(&mut self) -> Result<Node> {
+		self.binary(
+			&[
+				TokenKind::Compound(Symbol::Equal, Symbol::Equal),
+				TokenKind::Compound(Symbol::Bang, Symbol::Equal),
+			],
+			Self::comparison,
+		)
+	}
+
+	fn comparison(&mut self) -> Result<Node> {
+		self.binary(
+			&[
+				TokenKind::Symbol(Symbol::Lesser),
+				TokenKind::Compound(Symbol::Lesser, Symbol::Equal),
+				TokenKind::Symbol(Symbol::Greater),
+				TokenKind::Compound(Symbol::Greater, Symbol::Equal),
+			],
+			Self::bitwiseOr,
+		)
+	}
+
+	fn bitwiseOr(&mut self) -> Result<Node> {
+		self.binary(&[TokenKind::Symbol(Symbol::Pipe)], Self::bitwiseXor)
+	}
+
+	fn bitwiseXor(&mut self) -> Result<Node> {
+		self.binary(&[TokenKind::Symbol(Symbol::Caret)], Self::bitwiseAnd)
+	}
+
+	fn bitwiseAnd(&mut self) -> Result<Node> {
+		self.binary(&[TokenKind::Symbol(Symbol::Ampersand)], Self::shift)
+	}
+
+	fn shift(&mut self) -> Result<Node> {
+		self.binary(
+			&[
+				TokenKind::Compound(Symbol::Lesser, Symbol::Lesser),
+				TokenKind::Compound(Symbol::Greater, Symbol::Greater),
+			],
+			Self::term,
+		)
+	}
+
+	fn term(&mut self) -> Result<Node> {
+		self.binary(
+			&[TokenKind::Symbol(Symbol::Plus), TokenKind::Symbol(Symbol::Minus)],
+			Self::factor,
+		)
+	}
+
+	fn factor(&mut self) -> Result<Node> {
+		self.binary(
+			&[
+				TokenKind::Symbol(Symbol::Asterisk),
+				TokenKind::Symbol(Symbol::Slash),
+				TokenKind::Symbol(Symbol::Percent),
+			],
+			Self::unary,
+		)
+	}
+
+	/// Parse a left-associative chain of `operand (OPERATOR operand)*`.
+	fn binary(&mut self, operators: &[TokenKind], operand: fn(&mut Self) -> Result<Node>) -> Result<Node> {
+		let mut left = operand(self)?;
+
+		while operators.contains(&self.peek().kind()) {
+			let op = self.advance();
+			let right = operand(self)?;
+			let span = left.span().to(right.span());
+			left = Node::new(NodeKind::BinaryExpression(op, Box::new(left), Box::new(right)), span);
+		}
+
+		Ok(left)
+	}
+
+	fn unary(&mut self) -> Result<Node> {
+		let operators = [
+			TokenKind::Symbol(Symbol::Minus),
+			TokenKind::Symbol(Symbol::Bang),
+			TokenKind::Symbol(Symbol::Tilde),
+		];
+
+		if operators.contains(&self.peek().kind()) {
+			let op = self.advance();
+			let operand = self.unary()?;
+			let span = op.span().to(operand.span());
+
+			return Ok(Node::new(NodeKind::UnaryExpression(op, Box::new(operand)), span));
+		}
+
+		self.power()
+	}
+
+	/// `**` is right-associative and binds tighter than a unary operator on its left, but not on its right.
+	fn power(&mut self) -> Result<Node> {
+		let base = self.call()?;
+
+		if self.peek().kind() != TokenKind::Compound(Symbol::Asterisk, Symbol::Asterisk) {
+			return Ok(base);
+		}
+
+		let op = self.advance();
+		let exponent = self.unary()?;
+		let span = base.span().to(exponent.span());
+
+		Ok(Node::new(
+			NodeKind::BinaryExpression(op, Box::new(base), Box::new(exponent)),
+			span,
+		))
+	}
+
+	/// Calls `f(...)`, property access `a.b` and computed member access `a[b]`.
+	fn call(&mut self) -> Result<Node> {
+		let mut expr = self.primary()?;
+
+		loop {
+			let start = expr.span();
+
+			if self.matchSymbol(Symbol::LParen) {
+				let args = self.arguments()?;
+				expr = self.finish(start, NodeKind::CallExpression(Box::new(expr), args));
+			} else if self.matchSymbol(Symbol::Dot) {
+				let property = self.identifier("property name after '.'")?;
+				expr = self.finish(start, NodeKind::MemberExpression(false, Box::new(expr), Box::new(property)));
+			} else if self.matchSymbol(Symbol::LBracket) {
+				let property = self.expression()?;
+				self.expect(Symbol::RBracket, "']' after computed member")?;
+				expr = self.finish(start, NodeKind::MemberExpression(true, Box::new(expr), Box::new(property)));
+			} else {
+				break;
+			}
+		}
+
+		Ok(expr)
+	}
+
+	fn arguments(&mut self) -> Result<Vec<Node>> {
+		let mut args = Vec::new();
+
+		if !self.checkSymbol(Symbol::RParen) {
+			loop {
+				args.push(self.expression()?);
+				if !self.matchSymbol(Symbol::Comma) {
+					break;
+				}
+			}
+		}
+
+		self.expect(Symbol::RParen, "')' after arguments")?;
+
+		Ok(args)
+	}
+
+	fn primary(&mut self) -> Result<Node> {
+		let token = self.peek().clone();
+		let span = token.span();
+
+		match token.kind() {
+			TokenKind::Null | TokenKind::Bool | TokenKind::Int | TokenKind::Float | TokenKind::Char | TokenKind::Str => {
+				self.advance();
+				let value = Value2::try_from(token.value())
+					.map_err(|e| Diagnostic::error(ERR_INVALID_LITERAL, e.to_string()).withPrimary(span, "invalid literal"))?;
+				Ok(Node::new(NodeKind::Literal(Literal::new(value)), span))
+			}
+			TokenKind::Identifier => Ok(Node::new(NodeKind::Identifier(self.advance()), span)),
+			TokenKind::Keyword => match token.value() {
+				TokenValue::Keyword(Keyword::This) => {
+					self.advance();
+					Ok(Node::new(NodeKind::ThisExpression, span))
+				}
+				TokenValue::Keyword(Keyword::Super) => {
+					self.advance();
+					if !self.checkSymbol(Symbol::Dot) {
+						return self.error("expected '.' after 'super'");
+					}
+					Ok(Node::new(NodeKind::SuperExpression, span))
+				}
+				TokenValue::Keyword(Keyword::ReadLine) => self.readExpression(NodeKind::ReadLineExpression),
+				TokenValue::Keyword(Keyword::ReadChar) => self.readExpression(NodeKind::ReadCharExpression),
+				_ => self.error("expected expression"),
+			},
+			TokenKind::Symbol(Symbol::LParen) => {
+				self.advance();
+				let expr = self.expression()?;
+				self.expect(Symbol::RParen, "')' after expression")?;
+				Ok(expr)
+			}
+			// already reported by the tokenizer
+			TokenKind::Err(_) => {
+				self.advance();
+				Ok(Node::new(NodeKind::Error, span))
+			}
+			_ => self.error("expected expression"),
+		}
+	}
+
+	/// `readline()` and `readchar()`, which look like calls but take no arguments.
+	fn readExpression(&mut self, kind: NodeKind) -> Result<Node> {
+		let start = self.advance().span();
+		self.expect(Symbol::LParen, "'(' after built-in")?;
+		self.expect(Symbol::RParen, "')', built-in takes no arguments")?;
+		Ok(self.finish(start, kind))
+	}
+
+	/// Wrap `kind` in a node spanning from `start` to the end of the last consumed token.
+	fn finish(&self, start: Span, kind: NodeKind) -> Node {
+		Node::new(kind, start.to(self.previous().span()))
+	}
+
+	fn identifier(&mut self, what: &str) -> Result<Node> {
+		if self.peek().kind() == TokenKind::Identifier {
+			let token = self.advance();
+			let span = token.span();
+			Ok(Node::new(NodeKind::Identifier(token), span))
+		} else {
+			self.error(&format!("expected {what}"))
+		}
+	}
+
+	fn expect(&mut self, symbol: Symbol, what: &str) -> Result<Token> {
+		if self.checkSymbol(symbol) {
+			Ok(self.advance())
+		} else {
+			self.error(&format!("expected {what}"))
+		}
+	}
+
+	fn error<T>(&self, msg: &str) -> Result<T> {
+		let token = self.peek();
+
+		let found = match token.value() {
+			TokenValue::Keyword(kw) => format!("keyword '{kw}'"),
+			TokenValue::Identifier(name) => format!("identifier '{name}'"),
+			_ => token.kind().to_string(),
+		};
+
+		Err(Diagnostic::error(ERR_UNEXPECTED_TOKEN, format!("{msg}, found {found}")).withPrimary(token.span(), msg))
+	}
+
+	fn matchSymbol(&mut self, symbol: Symbol) -> bool {
+		let matches = self.checkSymbol(symbol);
+		if matches {
+			self.advance();
+		}
+		matches
+	}
+
+	fn matchKeyword(&mut self, keyword: Keyword) -> bool {
+		let matches = self.peekKeyword() == Some(keyword);
+		if matches {
+			self.advance();
+		}
+		matches
+	}
+
+	fn checkSymbol(&self, symbol: Symbol) -> bool {
+		self.peek().kind() == TokenKind::Symbol(symbol)
+	}
+
+	fn peekKeyword(&self) -> Option<Keyword> {
+		match self.peek().value() {
+			TokenValue::Keyword(kw) => Some(kw),
+			_ => None,
+		}
+	}
+
+	fn peek(&self) -> &Token {
+		&self.tokens[self.current]
+	}
+
+	fn previous(&self) -> &Token {
+		&self.tokens[self.current.saturating_sub(1)]
+	}
+
+	fn advance(&mut self) -> Token {
+		let token = self.peek().clone();
+		if !self.isAtEnd() {
+			self.current += 1;
+		}
+		token
+	}
+
+	fn isAtEnd(&self) -> bool {
+		self.peek().kind() == TokenKind::EOF
+	}
+}
+
+impl GenerateAST for Parser {
+	type Error = Diagnostics;
+
+	fn generate(tokens: Tokens) -> std::result::Result<AST, Self::Error> {
+		// the parser skips over invalid tokens, so report them here
+		let mut errors: Diagnostics = tokens
+			.iter()
+			.filter_map(|tk| match tk.kind() {
+				TokenKind::Err(msg) => Some(Diagnostic::error(ERR_SYNTAX, msg).withPrimary(tk.span(), "invalid token")),
+				_ => None,
+			})
+			.collect::<Vec<_>>()
+			.into();
+
+		let mut parser = Self::new(tokens);
+		let program = parser.program();
+		errors.extend(parser.errors);
+
+		if errors.hasErrors() {
+			Err(errors)
+		} else {
+			Ok(AST::new(program))
+		}
 	}
 }
@@ -0,0 +1,265 @@
+use std::{error, fmt, fmt::Write};
+
+use crate::{
+	diagnostic::{
+		Diagnostic,
+		ErrorCode,
+		ERR_ARITY_MISMATCH,
+		ERR_DIVISION_BY_ZERO,
+		ERR_INPUT,
+		ERR_INVALID_BYTECODE,
+		ERR_INVALID_EXIT_CODE,
+		ERR_INVALID_JUMP,
+		ERR_INVALID_OPERATION,
+		ERR_MISSING_HALT,
+		ERR_NOT_CALLABLE,
+		ERR_OUTPUT,
+		ERR_STACK_OVERFLOW,
+		ERR_STACK_UNDERFLOW,
+		ERR_UNDEFINED_PROPERTY,
+		ERR_UNDEFINED_VARIABLE,
+		ERR_UNKNOWN_INSTRUCTION,
+	},
+	span::Span,
+	token::TokenLine,
+	vm::MAX_FRAMES,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeErrorKind {
+	/// An operation was applied to values it doesn't support.
+	TypeError(String),
+	DivisionByZero,
+	/// An instruction needed more values than there were on the stack.
+	StackUnderflow,
+	StackOverflow,
+	/// The program halted with a value that isn't an int.
+	InvalidExitCode(String),
+	MissingHalt,
+	UnknownInstruction(u8),
+	/// The program could not be decoded, or refers to something that doesn't exist.
+	InvalidBytecode(String),
+	InvalidJump(String),
+	UndefinedVariable(String),
+	UndefinedProperty {
+		name:     String,
+		receiver: String,
+	},
+	NotCallable(String),
+	ArityMismatch {
+		callee:   String,
+		expected: u8,
+		found:    u8,
+	},
+	/// Printed text could not be written to the output.
+	Output(String),
+	/// The input could not be read, or wasn't valid UTF-8.
+	Input(String),
+}
+
+impl RuntimeErrorKind {
+	pub fn code(&self) -> ErrorCode {
+		match self {
+			Self::TypeError(_) => ERR_INVALID_OPERATION,
+			Self::DivisionByZero => ERR_DIVISION_BY_ZERO,
+			Self::StackUnderflow => ERR_STACK_UNDERFLOW,
+			Self::StackOverflow => ERR_STACK_OVERFLOW,
+			Self::InvalidExitCode(_) => ERR_INVALID_EXIT_CODE,
+			Self::MissingHalt => ERR_MISSING_HALT,
+			Self::UnknownInstruction(_) => ERR_UNKNOWN_INSTRUCTION,
+			Self::InvalidBytecode(_) => ERR_INVALID_BYTECODE,
+			Self::InvalidJump(_) => ERR_INVALID_JUMP,
+			Self::UndefinedVariable(_) => ERR_UNDEFINED_VARIABLE,
+			Self::UndefinedProperty { .. } => ERR_UNDEFINED_PROPERTY,
+			Self::NotCallable(_) => ERR_NOT_CALLABLE,
+			Self::ArityMismatch { .. } => ERR_ARITY_MISMATCH,
+			Self::Output(_) => ERR_OUTPUT,
+			Self::Input(_) => ERR_INPUT,
+		}
+	}
+}
+
+impl fmt::Display for RuntimeErrorKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::TypeError(msg) | Self::InvalidBytecode(msg) | Self::InvalidJump(msg) => write!(f, "{msg}"),
+			Self::DivisionByZero => write!(f, "cannot divide by 0"),
+			Self::StackUnderflow => write!(f, "stack underflow"),
+			Self::StackOverflow => write!(f, "stack overflow: more than {MAX_FRAMES} nested calls"),
+			Self::InvalidExitCode(value) => write!(f, "cannot use {value} as exit code"),
+			Self::MissingHalt => write!(f, "program exited without HALT instruction!"),
+			Self::UnknownInstruction(opcode) => write!(f, "unknown instruction 0x{opcode:02x}"),
+			Self::UndefinedVariable(name) => write!(f, "undefined variable '{name}'"),
+			Self::UndefinedProperty { name, receiver } => write!(f, "undefined property '{name}' on {receiver}"),
+			Self::NotCallable(value) => write!(f, "cannot call {value}, it is not a function"),
+			Self::ArityMismatch { callee, expected, found } => {
+				write!(f, "{callee} expects {expected} arguments but got {found}")
+			}
+			Self::Output(msg) => write!(f, "cannot write output: {msg}"),
+			Self::Input(msg) => write!(f, "cannot read input: {msg}"),
+		}
+	}
+}
+
+/// A function that was running when an error happened, and the instruction it was at: the failed instruction for
+/// the innermost one, the call to the next function for all others.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallSite {
+	function: String,
+	offset:   usize,
+	line:     Option<TokenLine>,
+}
+
+impl CallSite {
+	pub(crate) fn new(function: impl Into<String>, offset: usize, line: Option<TokenLine>) -> Self {
+		Self {
+			function: function.into(),
+			offset,
+			line,
+		}
+	}
+
+	pub fn function(&self) -> &str {
+		&self.function
+	}
+
+	pub fn offset(&self) -> usize {
+		self.offset
+	}
+
+	/// Source line of the instruction, if the program was compiled with spans or debug info.
+	pub fn line(&self) -> Option<TokenLine> {
+		self.line
+	}
+}
+
+/// An error that stopped a program, with the instruction it happened at. The `VM` keeps its stack and globals as
+/// they were when the error happened, until it is run again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+	kind:      RuntimeErrorKind,
+	offset:    usize,
+	span:      Option<Span>,
+	backtrace: Vec<CallSite>,
+	file:      Option<String>,
+}
+
+impl RuntimeError {
+	pub(crate) fn new(kind: RuntimeErrorKind, offset: usize, span: Option<Span>) -> Self {
+		Self {
+			kind,
+			offset,
+			span,
+			backtrace: Vec::new(),
+			file: None,
+		}
+	}
+
+	pub(crate) fn withBacktrace(mut self, backtrace: Vec<CallSite>) -> Self {
+		self.backtrace = backtrace;
+		self
+	}
+
+	pub(crate) fn withFile(mut self, file: Option<&str>) -> Self {
+		self.file = file.map(str::to_string);
+		self
+	}
+
+	pub fn kind(&self) -> &RuntimeErrorKind {
+		&self.kind
+	}
+
+	/// Byte offset of the instruction that failed.
+	pub fn offset(&self) -> usize {
+		self.offset
+	}
+
+	/// Source of the instruction that failed, if the program was compiled with spans or debug info.
+	pub fn span(&self) -> Option<Span> {
+		self.span
+	}
+
+	/// Functions that were running when the error happened, outermost first.
+	pub fn backtrace(&self) -> &[CallSite] {
+		&self.backtrace
+	}
+
+	/// Name of the source file the program was compiled from, if it is known.
+	pub fn file(&self) -> Option<&str> {
+		self.file.as_deref()
+	}
+
+	pub fn code(&self) -> ErrorCode {
+		self.kind.code()
+	}
+
+	pub fn message(&self) -> String {
+		self.kind.to_string()
+	}
+
+	pub fn diagnostic(&self) -> Diagnostic {
+		let diagnostic = Diagnostic::error(self.code(), self.message());
+
+		match self.span {
+			Some(span) => diagnostic.withPrimary(span, "while evaluating this"),
+			None => diagnostic.withNote(format!("at bytecode offset {:#06x}", self.offset)),
+		}
+	}
+
+	/// The backtrace and error, formatted like Python does:
+	///
+	/// ```text
+	/// Traceback (most recent call last):
+	///   File "main.nyo", line 5, in <script>
+	///   File "main.nyo", line 2, in inner
+	/// error[E0412]: cannot divide by 0
+	/// ```
+	///
+	/// Call sites without a known line show their bytecode offset instead. A call site repeated right after itself,
+	/// as in deep recursion, is only shown once.
+	pub fn traceback(&self) -> String {
+		let file = self.file.as_deref().unwrap_or("<unknown>");
+		let mut text = String::from("Traceback (most recent call last):\n");
+
+		let mut lines = self
+			.backtrace
+			.iter()
+			.map(|site| match site.line {
+				Some(line) => format!("  File \"{file}\", line {line}, in {}", site.function),
+				None => format!("  File \"{file}\", offset {:#06x}, in {}", site.offset, site.function),
+			})
+			.peekable();
+
+		while let Some(line) = lines.next() {
+			let mut repeats = 0;
+			while lines.next_if_eq(&line).is_some() {
+				repeats += 1;
+			}
+
+			writeln!(text, "{line}").unwrap();
+			if repeats > 0 {
+				writeln!(text, "  [Previous line repeated {repeats} more times]").unwrap();
+			}
+		}
+
+		writeln!(text, "error[{}]: {}", self.code(), self.kind).unwrap();
+		text
+	}
+}
+
+impl fmt::Display for RuntimeError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self.span {
+			Some(span) => write!(f, "{} ({span})", self.kind),
+			None => write!(f, "{} (at bytecode offset {:#06x})", self.kind, self.offset),
+		}
+	}
+}
+
+impl error::Error for RuntimeError {}
+
+impl From<RuntimeError> for Diagnostic {
+	fn from(e: RuntimeError) -> Self {
+		e.diagnostic()
+	}
+}
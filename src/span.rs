@@ -0,0 +1,90 @@
+use std::{fmt, ops::Range};
+
+use crate::token::TokenLine;
+
+pub type TokenColumn = u32;
+
+/// A single point in the source text. Lines and columns are 1-based, the byte offset is 0-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+	line:   TokenLine,
+	column: TokenColumn,
+	offset: usize,
+}
+
+impl Position {
+	pub fn new(line: TokenLine, column: TokenColumn, offset: usize) -> Self {
+		Self { line, column, offset }
+	}
+
+	pub fn line(&self) -> TokenLine {
+		self.line
+	}
+
+	pub fn column(&self) -> TokenColumn {
+		self.column
+	}
+
+	pub fn offset(&self) -> usize {
+		self.offset
+	}
+}
+
+impl Default for Position {
+	fn default() -> Self {
+		Self::new(1, 1, 0)
+	}
+}
+
+/// A region of source text. `end` points just past the last byte of the region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+	start: Position,
+	end:   Position,
+}
+
+impl Span {
+	pub fn new(start: Position, end: Position) -> Self {
+		Self { start, end }
+	}
+
+	pub fn start(&self) -> Position {
+		self.start
+	}
+
+	pub fn end(&self) -> Position {
+		self.end
+	}
+
+	pub fn line(&self) -> TokenLine {
+		self.start.line
+	}
+
+	pub fn column(&self) -> TokenColumn {
+		self.start.column
+	}
+
+	pub fn endLine(&self) -> TokenLine {
+		self.end.line
+	}
+
+	pub fn endColumn(&self) -> TokenColumn {
+		self.end.column
+	}
+
+	/// Byte range of the span in the source text.
+	pub fn range(&self) -> Range<usize> {
+		self.start.offset..self.end.offset
+	}
+
+	/// Smallest span covering both `self` and `other`.
+	pub fn to(&self, other: Span) -> Self {
+		Self::new(self.start.min(other.start), self.end.max(other.end))
+	}
+}
+
+impl fmt::Display for Span {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}:{}", self.start.line, self.start.column)
+	}
+}
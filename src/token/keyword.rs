@@ -1,4 +1,4 @@
-use std::io;
+use std::{fmt, io};
 
 use crate::Err;
 
@@ -16,6 +16,31 @@ pub enum Keyword {
 	This,
 	Super,
 	Print,
+	ReadLine,
+	ReadChar,
+}
+
+impl fmt::Display for Keyword {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let kw = match self {
+			Self::Let => "let",
+			Self::If => "if",
+			Self::Else => "else",
+			Self::While => "while",
+			Self::Do => "do",
+			Self::For => "for",
+			Self::Def => "def",
+			Self::Return => "return",
+			Self::Class => "class",
+			Self::This => "this",
+			Self::Super => "super",
+			Self::Print => "print",
+			Self::ReadLine => "readline",
+			Self::ReadChar => "readchar",
+		};
+
+		write!(f, "{kw}")
+	}
 }
 
 impl TryFrom<&str> for Keyword {
@@ -35,6 +60,8 @@ impl TryFrom<&str> for Keyword {
 			"this" => Ok(Self::This),
 			"super" => Ok(Self::Super),
 			"print" => Ok(Self::Print),
+			"readline" => Ok(Self::ReadLine),
+			"readchar" => Ok(Self::ReadChar),
 			other => Err!(format!("failed get Keyword from '{other}'")),
 		}
 	}
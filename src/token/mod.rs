@@ -1,4 +1,5 @@
 use self::{keyword::Keyword, tokenkind::TokenKind};
+use crate::span::Span;
 
 pub mod keyword;
 pub mod symbol;
@@ -25,18 +26,18 @@ pub enum TokenValue {
 	Error,
 }
 
-pub type TokenLine = u16;
+pub type TokenLine = u32;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
 	kind:  TokenKind,
 	value: TokenValue,
-	line:  TokenLine,
+	span:  Span,
 }
 
 impl Token {
-	pub fn new(kind: TokenKind, value: TokenValue, line: TokenLine) -> Self {
-		Self { kind, value, line }
+	pub fn new(kind: TokenKind, value: TokenValue, span: Span) -> Self {
+		Self { kind, value, span }
 	}
 
 	pub fn kind(&self) -> TokenKind {
@@ -48,6 +49,10 @@ impl Token {
 	}
 
 	pub fn line(&self) -> TokenLine {
-		self.line
+		self.span.line()
+	}
+
+	pub fn span(&self) -> Span {
+		self.span
 	}
 }
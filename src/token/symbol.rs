@@ -1,3 +1,5 @@
+use std::fmt;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Symbol {
 	Equal,
@@ -10,6 +12,7 @@ pub enum Symbol {
 	Pipe,
 	Caret,
 	Percent,
+	Tilde,
 	Lesser,
 	Greater,
 	Dot,
@@ -23,3 +26,35 @@ pub enum Symbol {
 	LBracket,
 	RBracket,
 }
+
+impl fmt::Display for Symbol {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let c = match self {
+			Self::Equal => '=',
+			Self::Bang => '!',
+			Self::Plus => '+',
+			Self::Minus => '-',
+			Self::Asterisk => '*',
+			Self::Slash => '/',
+			Self::Ampersand => '&',
+			Self::Pipe => '|',
+			Self::Caret => '^',
+			Self::Percent => '%',
+			Self::Tilde => '~',
+			Self::Lesser => '<',
+			Self::Greater => '>',
+			Self::Dot => '.',
+			Self::Colon => ':',
+			Self::Comma => ',',
+			Self::Semicolon => ';',
+			Self::LParen => '(',
+			Self::RParen => ')',
+			Self::LBrace => '{',
+			Self::RBrace => '}',
+			Self::LBracket => '[',
+			Self::RBracket => ']',
+		};
+
+		write!(f, "{c}")
+	}
+}
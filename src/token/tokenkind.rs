@@ -1,4 +1,4 @@
-use std::io;
+use std::{fmt, io};
 
 use super::symbol::Symbol;
 use crate::Err;
@@ -24,6 +24,25 @@ pub enum TokenKind {
 	Err(String),
 }
 
+impl fmt::Display for TokenKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Null => write!(f, "null"),
+			Self::Bool => write!(f, "bool"),
+			Self::Int => write!(f, "int"),
+			Self::Float => write!(f, "float"),
+			Self::Char => write!(f, "char"),
+			Self::Str => write!(f, "string"),
+			Self::Keyword => write!(f, "keyword"),
+			Self::Identifier => write!(f, "identifier"),
+			Self::Symbol(sym) => write!(f, "'{sym}'"),
+			Self::Compound(symA, symB) => write!(f, "'{symA}{symB}'"),
+			Self::EOF => write!(f, "end of file"),
+			Self::Err(e) => write!(f, "error ({e})"),
+		}
+	}
+}
+
 impl TryFrom<char> for TokenKind {
 	type Error = TryFromError;
 
@@ -35,8 +54,11 @@ impl TryFrom<char> for TokenKind {
 			'-' => Ok(Self::Symbol(Symbol::Minus)),
 			'*' => Ok(Self::Symbol(Symbol::Asterisk)),
 			'/' => Ok(Self::Symbol(Symbol::Slash)),
+			'&' => Ok(Self::Symbol(Symbol::Ampersand)),
+			'|' => Ok(Self::Symbol(Symbol::Pipe)),
 			'^' => Ok(Self::Symbol(Symbol::Caret)),
 			'%' => Ok(Self::Symbol(Symbol::Percent)),
+			'~' => Ok(Self::Symbol(Symbol::Tilde)),
 			'<' => Ok(Self::Symbol(Symbol::Lesser)),
 			'>' => Ok(Self::Symbol(Symbol::Greater)),
 			'.' => Ok(Self::Symbol(Symbol::Dot)),
@@ -69,6 +91,13 @@ impl TryFrom<&str> for TokenKind {
 		match value {
 			"&&" => Ok(Self::Compound(Symbol::Ampersand, Symbol::Ampersand)),
 			"||" => Ok(Self::Compound(Symbol::Pipe, Symbol::Pipe)),
+			"==" => Ok(Self::Compound(Symbol::Equal, Symbol::Equal)),
+			"!=" => Ok(Self::Compound(Symbol::Bang, Symbol::Equal)),
+			"<=" => Ok(Self::Compound(Symbol::Lesser, Symbol::Equal)),
+			">=" => Ok(Self::Compound(Symbol::Greater, Symbol::Equal)),
+			"**" => Ok(Self::Compound(Symbol::Asterisk, Symbol::Asterisk)),
+			"<<" => Ok(Self::Compound(Symbol::Lesser, Symbol::Lesser)),
+			">>" => Ok(Self::Compound(Symbol::Greater, Symbol::Greater)),
 			other if other.len() == 1 => Self::try_from(other.chars().nth(0).unwrap()),
 			other => Err!(format!("failed to get TokenKind from '{other}'")),
 		}
@@ -1,32 +1,59 @@
-use std::{
-	io::{ErrorKind, Result},
-	iter::Peekable,
-	slice::Iter,
-};
+use std::{iter::Peekable, slice::Iter};
 
 use crate::{
-	err,
-	token::{keyword::Keyword, symbol::Symbol, tokenkind::TokenKind, Token, TokenLine, TokenValue},
-	Err,
+	diagnostic::{
+		Diagnostic,
+		Diagnostics,
+		ErrorCode,
+		ERR_INVALID_CHAR,
+		ERR_INVALID_CHARACTER,
+		ERR_INVALID_ESCAPE,
+		ERR_INVALID_NUMBER,
+		ERR_UNTERMINATED_COMMENT,
+		ERR_UNTERMINATED_STRING,
+	},
+	span::{Position, Span},
+	token::{keyword::Keyword, symbol::Symbol, tokenkind::TokenKind, Token, TokenValue},
 };
 
 pub type Stream<'a> = Peekable<Iter<'a, u8>>;
 
+type Result<T> = std::result::Result<T, Diagnostic>;
+
 pub struct Tokenizer {
-	line: TokenLine,
+	/// Position of the next byte in the stream.
+	position: Position,
+	/// Position of the first byte of the token currently being made.
+	start:    Position,
 }
 
 impl Tokenizer {
-	pub fn tokenize(stream: Stream) -> Result<Vec<Token>> {
+	/// Tokenize the whole stream. Tokenizing continues past invalid tokens so that every error in the input is
+	/// reported at once.
+	pub fn tokenize(stream: Stream) -> std::result::Result<Vec<Token>, Diagnostics> {
+		let (tokens, errors) = Self::tokenizeRecovering(stream);
+
+		if errors.is_empty() {
+			Ok(tokens)
+		} else {
+			Err(errors)
+		}
+	}
+
+	/// Tokenize the whole stream, emitting invalid tokens as `TokenKind::Err` alongside the errors describing them.
+	pub fn tokenizeRecovering(stream: Stream) -> (Vec<Token>, Diagnostics) {
 		Self::new()._tokenize(stream)
 	}
 
 	fn new() -> Self {
-		Self { line: 1 }
+		Self {
+			position: Position::default(),
+			start:    Position::default(),
+		}
 	}
 
-	fn _tokenize(&mut self, mut stream: Stream) -> Result<Vec<Token>> {
-		let mut error = false;
+	fn _tokenize(&mut self, mut stream: Stream) -> (Vec<Token>, Diagnostics) {
+		let mut errors = Diagnostics::new();
 		let mut tokens = Vec::new();
 
 		loop {
@@ -35,9 +62,9 @@ impl Tokenizer {
 			let token = match token {
 				Ok(tk) => tk,
 				Err(e) => {
-					eprintln!("{e}");
-					error = true;
-					Token::new(TokenKind::Err(e.to_string()), TokenValue::Error, self.line)
+					let token = Token::new(TokenKind::Err(e.message().to_string()), TokenValue::Error, self.span());
+					errors.push(e);
+					token
 				}
 			};
 
@@ -48,21 +75,53 @@ impl Tokenizer {
 			tokens.push(token);
 		}
 
-		if error { Err!("failed to tokenize") } else { Ok(tokens) }
+		(tokens, errors)
+	}
+
+	/// Error spanning the token currently being made.
+	fn error(&self, code: ErrorCode, msg: impl Into<String>) -> Diagnostic {
+		let msg = msg.into();
+		Diagnostic::error(code, msg.clone()).withPrimary(self.span(), msg)
+	}
+
+	/// Consume the next byte, keeping track of where in the source we are.
+	fn bump(&mut self, stream: &mut Stream) -> Option<u8> {
+		let byte = *stream.next()?;
+
+		self.position = if byte == b'\n' {
+			Position::new(self.position.line().saturating_add(1), 1, self.position.offset() + 1)
+		} else {
+			let column = self.position.column().saturating_add(1);
+			Position::new(self.position.line(), column, self.position.offset() + 1)
+		};
+
+		Some(byte)
+	}
+
+	/// Span from the start of the current token up to the current position.
+	fn span(&self) -> Span {
+		Span::new(self.start, self.position)
+	}
+
+	fn token(&self, kind: TokenKind, value: TokenValue) -> Token {
+		Token::new(kind, value, self.span())
 	}
 
 	fn makeToken(&mut self, stream: &mut Stream) -> Result<Token> {
+		self.start = self.position;
+		self.skipTrivia(stream)?;
+		self.start = self.position;
+
 		let byte = if let Some(b) = stream.peek() {
 			*b
 		} else {
-			return Ok(Token::new(TokenKind::EOF, TokenValue::None, self.line));
+			return Ok(self.token(TokenKind::EOF, TokenValue::None));
 		};
 
-		let punctuation = vec![
-			b'=', b'!', b'+', b'-', b'*', b'/', b'&', b'|', b'^', b'%', b'<', b'>', b'.', b':', b',', b';', b'(', b')', b'{',
-			b'}', b'[', b']',
+		let punctuation = [
+			b'=', b'!', b'+', b'-', b'*', b'/', b'&', b'|', b'^', b'%', b'~', b'<', b'>', b'.', b':', b',', b';', b'(', b')',
+			b'{', b'}', b'[', b']',
 		];
-		let whitespace = vec![b' ', b'\t', b'\r'];
 
 		match byte {
 			b'0'..=b'9' => self.makeNumber(stream),
@@ -70,16 +129,33 @@ impl Tokenizer {
 			b'"' => self.makeString(stream),
 			b'_' | b'a'..=b'z' | b'A'..=b'Z' => self.makeKeyword(stream),
 			other if punctuation.contains(other) => self.makePunctuation(stream),
-			b'\n' => {
-				stream.next().unwrap();
-				self.line += 1;
-				self.makeToken(stream)
+			other => {
+				self.bump(stream);
+				let chr = self.makeUtf8(*other, stream);
+				Err(self.error(ERR_INVALID_CHARACTER, format!("invalid character '{chr}'")))
 			}
-			other if whitespace.contains(other) => {
-				stream.next().unwrap();
-				self.makeToken(stream)
+		}
+	}
+
+	/// Skip whitespace and comments.
+	fn skipTrivia(&mut self, stream: &mut Stream) -> Result<()> {
+		let whitespace = [b' ', b'\t', b'\r', b'\n'];
+
+		loop {
+			let mut ahead = stream.clone();
+
+			match (ahead.next(), ahead.next()) {
+				(Some(c), _) if whitespace.contains(c) => {
+					self.bump(stream);
+				}
+				(Some(b'/'), Some(b'/')) => self.skipSingleComment(stream)?,
+				(Some(b'/'), Some(b'*')) => {
+					self.bump(stream);
+					self.bump(stream);
+					self.skipMultiComment(stream)?;
+				}
+				_ => return Ok(()),
 			}
-			other => Err!(format!("invalid character {}", *other as char)),
 		}
 	}
 
@@ -92,50 +168,48 @@ impl Tokenizer {
 			if !c.is_ascii_digit() {
 				break;
 			}
-			numS.push(*stream.next().unwrap() as char);
+			numS.push(self.bump(stream).unwrap() as char);
 		}
 
 		if stream.peek() == Some(&&b'.') {
 			isFloat = true;
-			numS.push(*stream.next().unwrap() as char);
+			numS.push(self.bump(stream).unwrap() as char);
 			while let Some(c) = stream.peek() {
 				if !c.is_ascii_digit() {
 					break;
 				}
-				numS.push(*stream.next().unwrap() as char);
+				numS.push(self.bump(stream).unwrap() as char);
 			}
 		}
 
 		let token = if isFloat {
-			Token::new(
-				TokenKind::Float,
-				TokenValue::Float(numS.parse().expect("failed to parse float")),
-				self.line,
-			)
+			let value = numS
+				.parse()
+				.map_err(|e| self.error(ERR_INVALID_NUMBER, format!("invalid float literal: {e}")))?;
+			self.token(TokenKind::Float, TokenValue::Float(value))
 		} else {
-			Token::new(
-				TokenKind::Int,
-				TokenValue::Int(numS.parse().expect("failed to parse int")),
-				self.line,
-			)
+			let value = numS
+				.parse()
+				.map_err(|e| self.error(ERR_INVALID_NUMBER, format!("invalid int literal: {e}")))?;
+			self.token(TokenKind::Int, TokenValue::Int(value))
 		};
 
-		return Ok(token);
+		Ok(token)
 	}
 
 	fn makeKeyword(&mut self, stream: &mut Stream) -> Result<Token> {
 		let mut kw = String::new();
 
 		while let Some(c) = stream.peek() {
-			if !(c.is_ascii_alphabetic() || c.eq(&&b'_')) {
+			if !(c.is_ascii_alphanumeric() || c.eq(&&b'_')) {
 				break;
 			} else {
-				kw.push(*stream.next().unwrap() as char);
+				kw.push(self.bump(stream).unwrap() as char);
 			}
 		}
 
 		match Keyword::try_from(kw.to_string()) {
-			Ok(v) => Ok(Token::new(TokenKind::Keyword, TokenValue::Keyword(v), self.line)),
+			Ok(v) => Ok(self.token(TokenKind::Keyword, TokenValue::Keyword(v))),
 			Err(_) => {
 				let tokenKV = match kw.as_str() {
 					"null" => Some((TokenKind::Null, TokenValue::Null)),
@@ -145,8 +219,8 @@ impl Tokenizer {
 				};
 
 				let token = match tokenKV {
-					Some((kind, value)) => Token::new(kind, value, self.line),
-					None => Token::new(TokenKind::Identifier, TokenValue::Identifier(kw), self.line),
+					Some((kind, value)) => self.token(kind, value),
+					None => self.token(TokenKind::Identifier, TokenValue::Identifier(kw)),
 				};
 
 				Ok(token)
@@ -156,16 +230,16 @@ impl Tokenizer {
 
 	fn makeChar(&mut self, stream: &mut Stream) -> Result<Token> {
 		// consume '
-		stream.next();
+		self.bump(stream);
 
-		let chr = stream
-			.next()
-			.ok_or(err!("expected char, found EOF", ErrorKind::UnexpectedEof))?;
+		let chr = self
+			.bump(stream)
+			.ok_or_else(|| self.error(ERR_INVALID_CHAR, "expected char, found EOF"))?;
 
 		let value = if chr.eq(&b'\\') {
-			let escaped = stream
-				.next()
-				.ok_or(err!("expected char, found EOF", ErrorKind::UnexpectedEof))?;
+			let escaped = self
+				.bump(stream)
+				.ok_or_else(|| self.error(ERR_INVALID_CHAR, "expected char, found EOF"))?;
 
 			match escaped {
 				b'0' => '\0',
@@ -175,45 +249,60 @@ impl Tokenizer {
 				b'\'' => '\'',
 				b'\\' => '\\',
 				other => {
-					if stream
-						.next()
-						.ok_or(err!("expected closing quote, found EOF", ErrorKind::UnexpectedEof))?
-						.eq(&b'\'')
-					{
-						stream.next();
+					if stream.peek() == Some(&&b'\'') {
+						self.bump(stream);
 					}
 
-					return Err!(format!("invalid escaped char '\\{other}'"));
+					return Err(self.error(ERR_INVALID_ESCAPE, format!("invalid escaped char '\\{}'", other as char)));
 				}
 			}
 		} else {
-			*chr as char
+			self.makeUtf8(chr, stream)
+		};
+
+		match self.bump(stream) {
+			Some(b'\'') => Ok(self.token(TokenKind::Char, TokenValue::Char(value))),
+			Some(other) => Err(self.error(ERR_INVALID_CHAR, format!("expected closing quote, got '{}'", other as char))),
+			None => Err(self.error(ERR_INVALID_CHAR, "expected closing quote, found EOF")),
+		}
+	}
+
+	/// The char starting with `first`, taking the rest of its UTF-8 sequence from the stream.
+	fn makeUtf8(&mut self, first: u8, stream: &mut Stream) -> char {
+		let len = match first {
+			0xc0..=0xdf => 2,
+			0xe0..=0xef => 3,
+			0xf0..=0xf7 => 4,
+			_ => 1,
 		};
 
-		match stream.next() {
-			Some(v) if v.eq(&b'\'') => Ok(Token::new(TokenKind::Char, TokenValue::Char(value), self.line)),
-			Some(other) => Err!(format!("expected closing quote, got '{}'", *other as char)),
-			None => Err!("expected closing quote, found EOF", ErrorKind::UnexpectedEof),
+		let mut bytes = vec![first];
+		while bytes.len() < len && stream.peek().is_some_and(|b| *b & 0xc0 == 0x80) {
+			bytes.extend(self.bump(stream));
 		}
+
+		String::from_utf8_lossy(&bytes).chars().next().unwrap_or(char::REPLACEMENT_CHARACTER)
 	}
 
 	fn makeString(&mut self, stream: &mut Stream) -> Result<Token> {
 		// consume "
-		stream.next();
+		self.bump(stream);
 
-		let mut str = String::new();
+		// collect bytes so multi-byte UTF-8 characters stay intact
+		let mut bytes = Vec::new();
 
-		while let Some(chr) = stream.next() {
+		while let Some(chr) = self.bump(stream) {
 			match chr {
-				b'"' => return Ok(Token::new(TokenKind::Str, TokenValue::Str(str), self.line)),
-				b'\n' => {
-					self.line += 1;
-					str.push('\n');
+				b'"' => {
+					let str = String::from_utf8_lossy(&bytes).into_owned();
+					return Ok(self.token(TokenKind::Str, TokenValue::Str(str)));
 				}
 				b'\\' => {
-					let c = stream.next().ok_or(err!("unexpected EOF"))?;
+					let c = self
+						.bump(stream)
+						.ok_or_else(|| self.error(ERR_UNTERMINATED_STRING, "unterminated string"))?;
 
-					str += match c {
+					let escaped = match c {
 						b'0' => Ok("\0"),
 						b'n' => Ok("\n"),
 						b'r' => Ok("\r"),
@@ -224,55 +313,64 @@ impl Tokenizer {
 						b'f' => Ok("\\f"),
 						b'v' => Ok("\\v"),
 						b'\\' => Ok("\\"),
-						b'x' => todo!("ascii escape not supporter"),
-						other => Err!(format!("invalid escape sequence in string: '\\{}'", *other as char)),
+						other => Err(self.error(
+							ERR_INVALID_ESCAPE,
+							format!("invalid escape sequence in string: '\\{}'", other as char),
+						)),
 					}?;
+
+					bytes.extend(escaped.as_bytes());
 				}
-				other => str.push(*other as char),
+				other => bytes.push(other),
 			}
 		}
 
-		Err!("unterminated string", ErrorKind::UnexpectedEof)
+		Err(self.error(ERR_UNTERMINATED_STRING, "unterminated string"))
 	}
 
 	fn makePunctuation(&mut self, stream: &mut Stream) -> Result<Token> {
-		let p = stream.next().unwrap();
+		let p = self.bump(stream).unwrap();
 
 		match (p, stream.peek()) {
-			(b'/', Some(b'/')) => {
-				self.skipSingleComment(stream)?;
-				self.makeToken(stream)
+			(b'&', Some(b'&')) => {
+				self.bump(stream);
+				Ok(self.token(TokenKind::Compound(Symbol::Ampersand, Symbol::Ampersand), TokenValue::None))
+			}
+			(b'|', Some(b'|')) => {
+				self.bump(stream);
+				Ok(self.token(TokenKind::Compound(Symbol::Pipe, Symbol::Pipe), TokenValue::None))
 			}
-			(b'/', Some(b'*')) => {
-				stream.next();
-				self.skipMultiComment(stream)?;
-				self.makeToken(stream)
+			(b'*', Some(b'*')) | (b'<', Some(b'<')) | (b'>', Some(b'>')) => {
+				self.bump(stream);
+				let symbol = match p {
+					b'*' => Symbol::Asterisk,
+					b'<' => Symbol::Lesser,
+					_ => Symbol::Greater,
+				};
+				Ok(self.token(TokenKind::Compound(symbol.clone(), symbol), TokenValue::None))
+			}
+			(b'=' | b'!' | b'<' | b'>', Some(b'=')) => {
+				self.bump(stream);
+				let first = match p {
+					b'=' => Symbol::Equal,
+					b'!' => Symbol::Bang,
+					b'<' => Symbol::Lesser,
+					_ => Symbol::Greater,
+				};
+				Ok(self.token(TokenKind::Compound(first, Symbol::Equal), TokenValue::None))
 			}
-			(b'&', Some(b'&')) => Ok(Token::new(
-				TokenKind::Compound(Symbol::Ampersand, Symbol::Ampersand),
-				TokenValue::None,
-				self.line,
-			)),
-			(b'|', Some(b'|')) => Ok(Token::new(
-				TokenKind::Compound(Symbol::Pipe, Symbol::Pipe),
-				TokenValue::None,
-				self.line,
-			)),
 			(other, _) => {
-				let kind = TokenKind::try_from(*other)?;
-				Ok(Token::new(kind, TokenValue::None, self.line))
+				let kind = TokenKind::try_from(other)
+					.map_err(|_| self.error(ERR_INVALID_CHARACTER, format!("invalid character '{}'", other as char)))?;
+				Ok(self.token(kind, TokenValue::None))
 			}
 		}
 	}
 
 	fn skipSingleComment(&mut self, stream: &mut Stream) -> Result<()> {
-		while let Some(c) = stream.next() {
+		while let Some(c) = self.bump(stream) {
 			match c {
-				b'\n' => {
-					self.line += 1;
-					break;
-				}
-				b'\0' => break,
+				b'\n' | b'\0' => break,
 				_ => {}
 			}
 		}
@@ -282,18 +380,24 @@ impl Tokenizer {
 
 	fn skipMultiComment(&mut self, stream: &mut Stream) -> Result<()> {
 		loop {
-			let current = stream
-				.next()
-				.ok_or(err!("unterminated multi-line comment", ErrorKind::UnexpectedEof))?;
-
-			match (current, stream.next()) {
-				(b'*', Some(b'/')) => return Ok(()),
-				(b'/', Some(b'*')) => self.skipMultiComment(stream)?,
+			let current = self
+				.bump(stream)
+				.ok_or_else(|| self.error(ERR_UNTERMINATED_COMMENT, "unterminated multi-line comment"))?;
+
+			match (current, stream.peek()) {
+				(b'*', Some(b'/')) => {
+					self.bump(stream);
+					return Ok(());
+				}
+				(b'/', Some(b'*')) => {
+					self.bump(stream);
+					self.skipMultiComment(stream)?
+				}
 				(_, None) => break,
 				_ => {}
 			}
 		}
 
-		Err!("unterminated multi-line comment", ErrorKind::UnexpectedEof)
+		Err(self.error(ERR_UNTERMINATED_COMMENT, "unterminated multi-line comment"))
 	}
 }
@@ -1,3 +1,5 @@
+use std::fmt;
+
 use super::VALUE_CHAR;
 use crate::bytes::ToBytes;
 
@@ -16,18 +18,16 @@ impl Char {
 	}
 }
 
-impl ToString for Char {
-	fn to_string(&self) -> String {
-		self.value.to_string()
+impl fmt::Display for Char {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.value)
 	}
 }
 
 impl ToBytes for Char {
 	fn bytes(&self) -> Vec<u8> {
-		let mut bytes = Vec::with_capacity(2);
-		bytes.push(VALUE_CHAR);
-		bytes.push(self.value as u8);
-
+		let mut bytes = vec![VALUE_CHAR];
+		bytes.extend(u32::from(self.value).to_le_bytes());
 		bytes
 	}
 }
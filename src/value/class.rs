@@ -0,0 +1,113 @@
+use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
+
+use super::Value2;
+
+/// A class and its methods. Methods of the superclass are copied into the class when it inherits from it.
+#[derive(Debug)]
+pub struct Class {
+	name:    String,
+	methods: RefCell<HashMap<String, Value2>>,
+}
+
+impl Class {
+	pub fn new(name: impl Into<String>) -> Self {
+		Self {
+			name:    name.into(),
+			methods: RefCell::new(HashMap::new()),
+		}
+	}
+
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	pub fn method(&self, name: &str) -> Option<Value2> {
+		self.methods.borrow().get(name).cloned()
+	}
+
+	pub fn addMethod(&self, name: impl Into<String>, method: Value2) {
+		self.methods.borrow_mut().insert(name.into(), method);
+	}
+
+	/// Copy every method of `superclass` into this class.
+	pub fn inherit(&self, superclass: &Class) {
+		let methods = superclass.methods.borrow();
+		self.methods
+			.borrow_mut()
+			.extend(methods.iter().map(|(name, method)| (name.clone(), method.clone())));
+	}
+}
+
+impl fmt::Display for Class {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "<class {}>", self.name)
+	}
+}
+
+pub struct Instance {
+	class:  Rc<Class>,
+	fields: RefCell<HashMap<String, Value2>>,
+}
+
+impl Instance {
+	pub fn new(class: Rc<Class>) -> Self {
+		Self {
+			class,
+			fields: RefCell::new(HashMap::new()),
+		}
+	}
+
+	pub fn class(&self) -> &Rc<Class> {
+		&self.class
+	}
+
+	pub fn field(&self, name: &str) -> Option<Value2> {
+		self.fields.borrow().get(name).cloned()
+	}
+
+	pub fn setField(&self, name: impl Into<String>, value: Value2) {
+		self.fields.borrow_mut().insert(name.into(), value);
+	}
+}
+
+// fields may refer back to the instance, so they are left out
+impl fmt::Debug for Instance {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Instance")
+			.field("class", &self.class.name)
+			.finish_non_exhaustive()
+	}
+}
+
+impl fmt::Display for Instance {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "<{} instance>", self.class.name)
+	}
+}
+
+/// A method looked up on an instance, remembering the instance to call it with as `this`.
+#[derive(Debug)]
+pub struct BoundMethod {
+	receiver: Value2,
+	method:   Value2,
+}
+
+impl BoundMethod {
+	pub fn new(receiver: Value2, method: Value2) -> Self {
+		Self { receiver, method }
+	}
+
+	pub fn receiver(&self) -> &Value2 {
+		&self.receiver
+	}
+
+	pub fn method(&self) -> &Value2 {
+		&self.method
+	}
+}
+
+impl fmt::Display for BoundMethod {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.method)
+	}
+}
@@ -0,0 +1,40 @@
+use std::{cell::RefCell, fmt, rc::Rc};
+
+use super::{function::Function, Value2};
+
+/// A variable captured by a closure. It points into the stack while the function that declared it is running, and
+/// owns the value once that function has returned.
+#[derive(Debug)]
+pub enum Upvalue {
+	/// Index of the captured local on the stack.
+	Open(usize),
+	Closed(Value2),
+}
+
+/// A function together with the variables it captured from its enclosing functions. Closures only exist at
+/// runtime, they are created by the `Closure` instruction.
+#[derive(Debug)]
+pub struct Closure {
+	function: Rc<Function>,
+	upvalues: Vec<Rc<RefCell<Upvalue>>>,
+}
+
+impl Closure {
+	pub fn new(function: Rc<Function>, upvalues: Vec<Rc<RefCell<Upvalue>>>) -> Self {
+		Self { function, upvalues }
+	}
+
+	pub fn function(&self) -> &Rc<Function> {
+		&self.function
+	}
+
+	pub fn upvalue(&self, idx: usize) -> Option<&Rc<RefCell<Upvalue>>> {
+		self.upvalues.get(idx)
+	}
+}
+
+impl fmt::Display for Closure {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.function)
+	}
+}
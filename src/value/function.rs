@@ -0,0 +1,65 @@
+use std::fmt;
+
+use crate::bytes::{nameBytes, nameFromBytes, DecodeError, FromBytes, Reader, ToBytes};
+
+/// Byte offset of a function's first instruction in the program.
+pub type Address = u32;
+
+/// A compiled function. Its body lives in the program's code, starting at `entry`.
+///
+/// Functions are stored in the program's function table rather than the constant pool, each encoded as its name,
+/// arity (u8) and entry (u32).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+	name:  String,
+	arity: u8,
+	entry: Address,
+}
+
+impl Function {
+	pub fn new(name: impl Into<String>, arity: u8, entry: Address) -> Self {
+		Self {
+			name: name.into(),
+			arity,
+			entry,
+		}
+	}
+
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	pub fn arity(&self) -> u8 {
+		self.arity
+	}
+
+	pub fn entry(&self) -> Address {
+		self.entry
+	}
+}
+
+impl fmt::Display for Function {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "<fn {}>", self.name)
+	}
+}
+
+impl ToBytes for Function {
+	fn bytes(&self) -> Vec<u8> {
+		let mut bytes = nameBytes(&self.name);
+		bytes.push(self.arity);
+		bytes.extend(self.entry.to_le_bytes());
+
+		bytes
+	}
+}
+
+impl FromBytes for Function {
+	fn fromBytes(bytes: &mut Reader) -> Result<Self, DecodeError> {
+		let name = nameFromBytes(bytes)?;
+		let arity = bytes.takeByte()?;
+		let entry = Address::from_le_bytes(bytes.takeArray()?);
+
+		Ok(Self::new(name, arity, entry))
+	}
+}
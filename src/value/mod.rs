@@ -1,10 +1,16 @@
 use std::{
+	cmp::Ordering,
+	fmt,
 	io,
-	ops::{Add, Div, Mul, Sub},
+	ops::{Add, BitAnd, BitOr, BitXor, Div, Mul, Neg, Not, Rem, Shl, Shr, Sub},
+	rc::Rc,
 };
 
 use self::{
 	char::Char,
+	class::{BoundMethod, Class, Instance},
+	closure::Closure,
+	function::Function,
 	number::{
 		float::{Float32, Float64},
 		int::{Int32, Int64},
@@ -12,12 +18,15 @@ use self::{
 	string::String as NString,
 };
 use crate::{
-	bytes::{FromBytes, ToBytes},
+	bytes::{DecodeError, FromBytes, Reader, ToBytes},
 	token::TokenValue,
 	Err,
 };
 
 pub mod char;
+pub mod class;
+pub mod closure;
+pub mod function;
 pub mod number;
 pub mod string;
 pub mod traits;
@@ -44,23 +53,178 @@ pub enum Value2 {
 	Float64(Float64),
 	Char(Char),
 	String(NString),
+	Function(Rc<Function>),
+	Closure(Rc<Closure>),
+	Class(Rc<Class>),
+	Instance(Rc<Instance>),
+	BoundMethod(Rc<BoundMethod>),
 }
 
-impl ToString for Value2 {
-	fn to_string(&self) -> String {
+impl Value2 {
+	/// Name of the value's type, for error messages.
+	pub fn typeName(&self) -> &'static str {
 		match self {
-			Self::Null => String::from("null"),
-			Self::True => String::from("true"),
-			Self::False => String::from("false"),
-			Self::Int32(v) => v.to_string(),
-			Self::Int64(v) => v.to_string(),
-			Self::Float32(v) => v.to_string(),
-			Self::Float64(v) => v.to_string(),
-			Self::Char(v) => v.to_string(),
-			Self::String(v) => v.to_string(),
-
-			#[allow(unreachable_patterns)]
-			other => other.to_string(),
+			Self::Null => "null",
+			Self::True | Self::False => "bool",
+			Self::Int32(_) => "int32",
+			Self::Int64(_) => "int64",
+			Self::Float32(_) => "float32",
+			Self::Float64(_) => "float64",
+			Self::Char(_) => "char",
+			Self::String(_) => "string",
+			Self::Function(_) | Self::Closure(_) => "function",
+			Self::Class(_) => "class",
+			Self::Instance(_) => "instance",
+			Self::BoundMethod(_) => "method",
+		}
+	}
+
+	/// `null`, `false` and zero are falsy, everything else is truthy.
+	pub fn isTruthy(&self) -> bool {
+		match self {
+			Self::Null | Self::False => false,
+			Self::Int32(v) => v.value() != 0,
+			Self::Int64(v) => v.value() != 0,
+			Self::Float32(v) => v.value() != 0.0,
+			Self::Float64(v) => v.value() != 0.0,
+			_ => true,
+		}
+	}
+
+	/// Numbers are equal to each other if they have the same value, regardless of their type. Classes, instances
+	/// and closures are only equal to themselves. Values of different types are never equal.
+	pub fn equals(&self, other: &Self) -> bool {
+		if let (Some(a), Some(b)) = (self.number(), other.number()) {
+			return a.compare(b) == Some(Ordering::Equal);
+		}
+
+		match (self, other) {
+			(Self::Null, Self::Null) | (Self::True, Self::True) | (Self::False, Self::False) => true,
+			(Self::Char(a), Self::Char(b)) => a.value() == b.value(),
+			(Self::String(a), Self::String(b)) => a.asStr() == b.asStr(),
+			(Self::Function(a), Self::Function(b)) => a == b,
+			(Self::Closure(a), Self::Closure(b)) => Rc::ptr_eq(a, b),
+			(Self::Class(a), Self::Class(b)) => Rc::ptr_eq(a, b),
+			(Self::Instance(a), Self::Instance(b)) => Rc::ptr_eq(a, b),
+			(Self::BoundMethod(a), Self::BoundMethod(b)) => a.receiver().equals(b.receiver()) && a.method().equals(b.method()),
+			_ => false,
+		}
+	}
+
+	/// Ordering of two numbers, chars or strings, `None` if either is NaN. Other values can't be ordered.
+	pub fn compare(&self, other: &Self) -> io::Result<Option<Ordering>> {
+		if let (Some(a), Some(b)) = (self.number(), other.number()) {
+			return Ok(a.compare(b));
+		}
+
+		match (self, other) {
+			(Self::Char(a), Self::Char(b)) => Ok(Some(a.value().cmp(&b.value()))),
+			(Self::String(a), Self::String(b)) => Ok(Some(a.asStr().cmp(b.asStr()))),
+			(a, b) => Err!(format!("cannot compare {} with {}", a.typeName(), b.typeName())),
+		}
+	}
+
+	/// Raise a number to a power. Ints raised to a negative power become floats.
+	pub fn pow(self, rhs: Self) -> io::Result<Self> {
+		match Operands::of(&self, &rhs) {
+			Some(Operands::Int32(a, b)) => Ok(match u32::try_from(b) {
+				Ok(exp) => a.wrapping_pow(exp).into(),
+				Err(_) => f64::from(a).powf(f64::from(b)).into(),
+			}),
+			Some(Operands::Int64(a, b)) => Ok(match u32::try_from(b) {
+				Ok(exp) => a.wrapping_pow(exp).into(),
+				Err(_) => (a as f64).powf(b as f64).into(),
+			}),
+			Some(Operands::Float32(a, b)) => Ok(a.powf(b).into()),
+			Some(Operands::Float64(a, b)) => Ok(a.powf(b).into()),
+			None => invalidBinary("**", &self, &rhs),
+		}
+	}
+
+	fn number(&self) -> Option<Number> {
+		match self {
+			Self::Int32(v) => Some(Number::Int(v.value().into())),
+			Self::Int64(v) => Some(Number::Int(v.value())),
+			Self::Float32(v) => Some(Number::Float(v.value().into())),
+			Self::Float64(v) => Some(Number::Float(v.value())),
+			_ => None,
+		}
+	}
+}
+
+/// A number of any type, widened for comparisons.
+#[derive(Clone, Copy)]
+enum Number {
+	Int(i64),
+	Float(f64),
+}
+
+/// Two numbers converted to the wider of their types.
+enum Operands {
+	Int32(i32, i32),
+	Int64(i64, i64),
+	Float32(f32, f32),
+	Float64(f64, f64),
+}
+
+impl Operands {
+	fn of(a: &Value2, b: &Value2) -> Option<Self> {
+		use Value2 as V;
+
+		match (a, b) {
+			(V::Int32(a), V::Int32(b)) => Some(Self::Int32(a.value(), b.value())),
+			(V::Float32(a), V::Float32(b)) => Some(Self::Float32(a.value(), b.value())),
+			_ => match (a.number()?, b.number()?) {
+				(Number::Int(a), Number::Int(b)) => Some(Self::Int64(a, b)),
+				(Number::Int(a), Number::Float(b)) => Some(Self::Float64(a as f64, b)),
+				(Number::Float(a), Number::Int(b)) => Some(Self::Float64(a, b as f64)),
+				(Number::Float(a), Number::Float(b)) => Some(Self::Float64(a, b)),
+			},
+		}
+	}
+}
+
+impl Number {
+	fn compare(self, other: Self) -> Option<Ordering> {
+		match (self, other) {
+			(Self::Int(a), Self::Int(b)) => Some(a.cmp(&b)),
+			(Self::Int(a), Self::Float(b)) => (a as f64).partial_cmp(&b),
+			(Self::Float(a), Self::Int(b)) => a.partial_cmp(&(b as f64)),
+			(Self::Float(a), Self::Float(b)) => a.partial_cmp(&b),
+		}
+	}
+}
+
+/// Integer division by zero, told apart from other failed operations by its `InvalidInput` kind.
+fn divisionByZero() -> io::Result<Value2> {
+	Err!("cannot divide by 0", io::ErrorKind::InvalidInput)
+}
+
+fn invalidBinary(op: &str, a: &Value2, b: &Value2) -> io::Result<Value2> {
+	Err!(format!("cannot use '{op}' on {} and {}", a.typeName(), b.typeName()))
+}
+
+fn invalidUnary(op: &str, v: &Value2) -> io::Result<Value2> {
+	Err!(format!("cannot use '{op}' on {}", v.typeName()))
+}
+
+impl fmt::Display for Value2 {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Null => write!(f, "null"),
+			Self::True => write!(f, "true"),
+			Self::False => write!(f, "false"),
+			Self::Int32(v) => write!(f, "{v}"),
+			Self::Int64(v) => write!(f, "{v}"),
+			Self::Float32(v) => write!(f, "{v}"),
+			Self::Float64(v) => write!(f, "{v}"),
+			Self::Char(v) => write!(f, "{v}"),
+			Self::String(v) => write!(f, "{v}"),
+			Self::Function(v) => write!(f, "{v}"),
+			Self::Closure(v) => write!(f, "{v}"),
+			Self::Class(v) => write!(f, "{v}"),
+			Self::Instance(v) => write!(f, "{v}"),
+			Self::BoundMethod(v) => write!(f, "{v}"),
 		}
 	}
 }
@@ -79,9 +243,10 @@ impl ToBytes for Value2 {
 			Self::Float64(v) => bytes.extend(v.bytes()),
 			Self::Char(v) => bytes.extend(v.bytes()),
 			Self::String(v) => bytes.extend(v.bytes()),
-
-			#[allow(unreachable_patterns)]
-			other => todo!("{other:?}.toBytes()"),
+			Self::Function(_) => unreachable!("{self} is stored in the function table"),
+			Self::Closure(_) | Self::Class(_) | Self::Instance(_) | Self::BoundMethod(_) => {
+				unreachable!("{self} only exists at runtime")
+			}
 		}
 
 		bytes
@@ -89,48 +254,19 @@ impl ToBytes for Value2 {
 }
 
 impl FromBytes for Value2 {
-	fn fromBytes(bytes: &mut Vec<u8>) -> Self {
-		match bytes.remove(0) {
+	fn fromBytes(bytes: &mut Reader) -> Result<Self, DecodeError> {
+		Ok(match bytes.takeByte()? {
 			VALUE_NULL => Self::Null,
 			VALUE_TRUE => Self::True,
 			VALUE_FALSE => Self::False,
-			VALUE_INT32 => {
-				let mut valBytes = [0; 4];
-				(0..4).for_each(|idx| valBytes[idx] = bytes.remove(0));
-				Self::Int32(Int32::new(i32::from_le_bytes(valBytes)))
-			}
-			VALUE_INT64 => {
-				let mut valBytes = [0; 8];
-				(0..8).for_each(|idx| valBytes[idx] = bytes.remove(0));
-				Self::Int64(Int64::new(i64::from_le_bytes(valBytes)))
-			}
-			VALUE_FLOAT32 => {
-				let mut valBytes = [0; 4];
-				(0..4).for_each(|idx| valBytes[idx] = bytes.remove(0));
-				Self::Float32(f32::from_le_bytes(valBytes).into())
-			}
-			VALUE_FLOAT64 => {
-				let mut valBytes = [0; 8];
-				(0..8).for_each(|idx| valBytes[idx] = bytes.remove(0));
-				Self::Float64(f64::from_le_bytes(valBytes).into())
-			}
-			VALUE_CHAR => Self::Char(bytes.remove(0).into()),
-			VALUE_STR => {
-				let strSizeBytes = [bytes.remove(0), bytes.remove(0)];
-				let strSize = u16::from_le_bytes(strSizeBytes).into();
-
-				let mut strBytes = Vec::with_capacity(strSize);
-
-				(0..strSize).for_each(|_| strBytes.push(bytes.remove(0)));
-
-				let s = String::from_utf8(strBytes).expect("failed to read string");
-
-				Self::String(s.into())
-			}
-
-			#[allow(unreachable_patterns)]
-			other => todo!("{other:x?}"),
-		}
+			VALUE_INT32 => Self::Int32(Int32::new(i32::from_le_bytes(bytes.takeArray()?))),
+			VALUE_INT64 => Self::Int64(Int64::new(i64::from_le_bytes(bytes.takeArray()?))),
+			VALUE_FLOAT32 => Self::Float32(f32::from_le_bytes(bytes.takeArray()?).into()),
+			VALUE_FLOAT64 => Self::Float64(f64::from_le_bytes(bytes.takeArray()?).into()),
+			VALUE_CHAR => Self::Char(bytes.takeChar()?.into()),
+			VALUE_STR => Self::String(bytes.takeString()?.into()),
+			other => return Err(bytes.unknownTag("value", other)),
+		})
 	}
 }
 
@@ -138,30 +274,15 @@ impl Add for Value2 {
 	type Output = io::Result<Self>;
 
 	fn add(self, rhs: Self) -> Self::Output {
-		match (self, rhs) {
-			(Self::Int32(a), Self::Int32(b)) => Ok(Self::Int32(a + b.value())),
-			(Self::Int32(a), Self::Int64(b)) => Ok(Self::Int64(a + b)),
-			(Self::Int32(a), Self::True) => Ok(Self::Int32(a + 1)),
-			(Self::Int32(a), Self::False) => Ok(Self::Int32(a)),
-
-			(Self::Int64(a), Self::Int32(b)) => Ok(Self::Int64(a + b.value())),
-			(Self::Int64(a), Self::Int64(b)) => Ok(Self::Int64(a + b.value())),
-			(Self::Int64(a), Self::True) => Ok(Self::Int64(a + 1)),
-			(Self::Int64(a), Self::False) => Ok(Self::Int64(a)),
-
-			(Self::Float32(_a), Self::Int32(_b)) => todo!(),
-			(Self::Float32(_a), Self::Int64(_b)) => todo!(),
-			(Self::Float32(a), Self::Float32(b)) => Ok(Self::Float32(a + b.value())),
-			(Self::Float32(a), Self::Float64(b)) => Ok(Self::Float64(a + b)),
-
-			(Self::Float64(a), Self::Int32(b)) => Ok(Self::Float64(a + f64::from(b.value()))),
-			(Self::Float64(_a), Self::Int64(_b)) => todo!(),
-			(Self::Float64(a), Self::Float32(b)) => Ok(Self::Float64(a + b.value())),
-			(Self::Float64(a), Self::Float64(b)) => Ok(Self::Float64(a + b.value())),
-
-			(Self::String(a), b) => Ok(Self::String(a + b)),
-
-			(otherA, otherB) => Err!(format!("invalid operation {otherA:?} + {otherB:?}")),
+		match Operands::of(&self, &rhs) {
+			Some(Operands::Int32(a, b)) => Ok(a.wrapping_add(b).into()),
+			Some(Operands::Int64(a, b)) => Ok(a.wrapping_add(b).into()),
+			Some(Operands::Float32(a, b)) => Ok((a + b).into()),
+			Some(Operands::Float64(a, b)) => Ok((a + b).into()),
+			None => match (self, rhs) {
+				(Self::String(a), b) => Ok(Self::String(a + b)),
+				(otherA, otherB) => invalidBinary("+", &otherA, &otherB),
+			},
 		}
 	}
 }
@@ -170,28 +291,12 @@ impl Sub for Value2 {
 	type Output = io::Result<Self>;
 
 	fn sub(self, rhs: Self) -> Self::Output {
-		match (self, rhs) {
-			(Self::Int32(a), Self::Int32(b)) => Ok(Self::Int32(a - b.value())),
-			(Self::Int32(a), Self::Int64(b)) => Ok(Self::Int64(a - b)),
-			(Self::Int32(a), Self::True) => Ok(Self::Int32(a - 1)),
-			(Self::Int32(a), Self::False) => Ok(Self::Int32(a)),
-
-			(Self::Int64(a), Self::Int32(b)) => Ok(Self::Int64(a - b.value())),
-			(Self::Int64(a), Self::Int64(b)) => Ok(Self::Int64(a - b.value())),
-			(Self::Int64(a), Self::True) => Ok(Self::Int64(a - 1)),
-			(Self::Int64(a), Self::False) => Ok(Self::Int64(a)),
-
-			(Self::Float32(_a), Self::Int32(_b)) => todo!(),
-			(Self::Float32(_a), Self::Int64(_b)) => todo!(),
-			(Self::Float32(a), Self::Float32(b)) => Ok(Self::Float32(a - b.value())),
-			(Self::Float32(a), Self::Float64(b)) => Ok(Self::Float64(Float64::from(a) - b.value())),
-
-			(Self::Float64(a), Self::Int32(b)) => Ok(Self::Float64(a - f64::from(b.value()))),
-			(Self::Float64(_a), Self::Int64(_b)) => todo!(),
-			(Self::Float64(a), Self::Float32(b)) => Ok(Self::Float64(a - b.value())),
-			(Self::Float64(a), Self::Float64(b)) => Ok(Self::Float64(a - b.value())),
-
-			(otherA, otherB) => Err!(format!("invalid operation {otherA:?} - {otherB:?}")),
+		match Operands::of(&self, &rhs) {
+			Some(Operands::Int32(a, b)) => Ok(a.wrapping_sub(b).into()),
+			Some(Operands::Int64(a, b)) => Ok(a.wrapping_sub(b).into()),
+			Some(Operands::Float32(a, b)) => Ok((a - b).into()),
+			Some(Operands::Float64(a, b)) => Ok((a - b).into()),
+			None => invalidBinary("-", &self, &rhs),
 		}
 	}
 }
@@ -200,28 +305,12 @@ impl Mul for Value2 {
 	type Output = io::Result<Self>;
 
 	fn mul(self, rhs: Self) -> Self::Output {
-		match (self, rhs) {
-			(Self::Int32(a), Self::Int32(b)) => Ok(Self::Int32(a * b.value())),
-			(Self::Int32(a), Self::Int64(b)) => Ok(Self::Int64(a * b)),
-			(Self::Int32(a), Self::True) => Ok(Self::Int32(a)),
-			(Self::Int32(_), Self::False) => Ok(Self::Int32(0.into())),
-
-			(Self::Int64(a), Self::Int32(b)) => Ok(Self::Int64(a * b.value())),
-			(Self::Int64(a), Self::Int64(b)) => Ok(Self::Int64(a * b.value())),
-			(Self::Int64(a), Self::True) => Ok(Self::Int64(a)),
-			(Self::Int64(_), Self::False) => Ok(Self::Int64(0.into())),
-
-			(Self::Float32(_a), Self::Int32(_b)) => todo!(),
-			(Self::Float32(_a), Self::Int64(_b)) => todo!(),
-			(Self::Float32(a), Self::Float32(b)) => Ok(Self::Float32(a * b.value())),
-			(Self::Float32(a), Self::Float64(b)) => Ok(Self::Float64(Float64::from(a) * b.value())),
-
-			(Self::Float64(a), Self::Int32(b)) => Ok(Self::Float64(a * f64::from(b.value()))),
-			(Self::Float64(_a), Self::Int64(_b)) => todo!(),
-			(Self::Float64(a), Self::Float32(b)) => Ok(Self::Float64(a * b.value())),
-			(Self::Float64(a), Self::Float64(b)) => Ok(Self::Float64(a * b.value())),
-
-			(otherA, otherB) => Err!(format!("invalid operation {otherA:?} * {otherB:?}")),
+		match Operands::of(&self, &rhs) {
+			Some(Operands::Int32(a, b)) => Ok(a.wrapping_mul(b).into()),
+			Some(Operands::Int64(a, b)) => Ok(a.wrapping_mul(b).into()),
+			Some(Operands::Float32(a, b)) => Ok((a * b).into()),
+			Some(Operands::Float64(a, b)) => Ok((a * b).into()),
+			None => invalidBinary("*", &self, &rhs),
 		}
 	}
 }
@@ -230,26 +319,137 @@ impl Div for Value2 {
 	type Output = io::Result<Self>;
 
 	fn div(self, rhs: Self) -> Self::Output {
-		match (self, rhs) {
-			(Self::Int32(a), Self::Int32(b)) => Ok(Self::Int32(a / b.value())),
-			(Self::Int32(a), Self::Int64(b)) => Ok(Self::Int64(a / b)),
-			(Self::Int32(a), Self::True) => Ok(Self::Int32(a)),
-
-			(Self::Int64(a), Self::Int32(b)) => Ok(Self::Int64(a / b.value())),
-			(Self::Int64(a), Self::Int64(b)) => Ok(Self::Int64(a / b.value())),
-			(Self::Int64(a), Self::True) => Ok(Self::Int64(a)),
-
-			(Self::Float32(_a), Self::Int32(_b)) => todo!(),
-			(Self::Float32(_a), Self::Int64(_b)) => todo!(),
-			(Self::Float32(a), Self::Float32(b)) => Ok(Self::Float32(a / b.value())),
-			(Self::Float32(a), Self::Float64(b)) => Ok(Self::Float64(Float64::from(a) / b.value())),
-
-			(Self::Float64(a), Self::Int32(b)) => Ok(Self::Float64(a / f64::from(b.value()))),
-			(Self::Float64(_a), Self::Int64(_b)) => todo!(),
-			(Self::Float64(a), Self::Float32(b)) => Ok(Self::Float64(a / b.value())),
-			(Self::Float64(a), Self::Float64(b)) => Ok(Self::Float64(a / b.value())),
-
-			(otherA, otherB) => Err!(format!("invalid operation {otherA:?} / {otherB:?}")),
+		match Operands::of(&self, &rhs) {
+			Some(Operands::Int32(_, 0) | Operands::Int64(_, 0)) => divisionByZero(),
+			Some(Operands::Int32(a, b)) => Ok(a.wrapping_div(b).into()),
+			Some(Operands::Int64(a, b)) => Ok(a.wrapping_div(b).into()),
+			Some(Operands::Float32(a, b)) => Ok((a / b).into()),
+			Some(Operands::Float64(a, b)) => Ok((a / b).into()),
+			None => invalidBinary("/", &self, &rhs),
+		}
+	}
+}
+
+impl Rem for Value2 {
+	type Output = io::Result<Self>;
+
+	fn rem(self, rhs: Self) -> Self::Output {
+		match Operands::of(&self, &rhs) {
+			Some(Operands::Int32(_, 0) | Operands::Int64(_, 0)) => divisionByZero(),
+			Some(Operands::Int32(a, b)) => Ok(a.wrapping_rem(b).into()),
+			Some(Operands::Int64(a, b)) => Ok(a.wrapping_rem(b).into()),
+			Some(Operands::Float32(a, b)) => Ok((a % b).into()),
+			Some(Operands::Float64(a, b)) => Ok((a % b).into()),
+			None => invalidBinary("%", &self, &rhs),
+		}
+	}
+}
+
+impl BitAnd for Value2 {
+	type Output = io::Result<Self>;
+
+	fn bitand(self, rhs: Self) -> Self::Output {
+		match (&self, &rhs) {
+			(Self::True | Self::False, Self::True | Self::False) => Ok((self.isTruthy() & rhs.isTruthy()).into()),
+			_ => match Operands::of(&self, &rhs) {
+				Some(Operands::Int32(a, b)) => Ok((a & b).into()),
+				Some(Operands::Int64(a, b)) => Ok((a & b).into()),
+				_ => invalidBinary("&", &self, &rhs),
+			},
+		}
+	}
+}
+
+impl BitOr for Value2 {
+	type Output = io::Result<Self>;
+
+	fn bitor(self, rhs: Self) -> Self::Output {
+		match (&self, &rhs) {
+			(Self::True | Self::False, Self::True | Self::False) => Ok((self.isTruthy() | rhs.isTruthy()).into()),
+			_ => match Operands::of(&self, &rhs) {
+				Some(Operands::Int32(a, b)) => Ok((a | b).into()),
+				Some(Operands::Int64(a, b)) => Ok((a | b).into()),
+				_ => invalidBinary("|", &self, &rhs),
+			},
+		}
+	}
+}
+
+impl BitXor for Value2 {
+	type Output = io::Result<Self>;
+
+	fn bitxor(self, rhs: Self) -> Self::Output {
+		match (&self, &rhs) {
+			(Self::True | Self::False, Self::True | Self::False) => Ok((self.isTruthy() ^ rhs.isTruthy()).into()),
+			_ => match Operands::of(&self, &rhs) {
+				Some(Operands::Int32(a, b)) => Ok((a ^ b).into()),
+				Some(Operands::Int64(a, b)) => Ok((a ^ b).into()),
+				_ => invalidBinary("^", &self, &rhs),
+			},
+		}
+	}
+}
+
+impl Shl for Value2 {
+	type Output = io::Result<Self>;
+
+	fn shl(self, rhs: Self) -> Self::Output {
+		let shifted = match Operands::of(&self, &rhs) {
+			Some(Operands::Int32(a, b)) => u32::try_from(b).ok().and_then(|b| a.checked_shl(b)).map(Self::from),
+			Some(Operands::Int64(a, b)) => u32::try_from(b).ok().and_then(|b| a.checked_shl(b)).map(Self::from),
+			_ => return invalidBinary("<<", &self, &rhs),
+		};
+
+		match shifted {
+			Some(v) => Ok(v),
+			None => Err!(format!("cannot shift {self} by {rhs} bits")),
+		}
+	}
+}
+
+impl Shr for Value2 {
+	type Output = io::Result<Self>;
+
+	fn shr(self, rhs: Self) -> Self::Output {
+		let shifted = match Operands::of(&self, &rhs) {
+			Some(Operands::Int32(a, b)) => u32::try_from(b).ok().and_then(|b| a.checked_shr(b)).map(Self::from),
+			Some(Operands::Int64(a, b)) => u32::try_from(b).ok().and_then(|b| a.checked_shr(b)).map(Self::from),
+			_ => return invalidBinary(">>", &self, &rhs),
+		};
+
+		match shifted {
+			Some(v) => Ok(v),
+			None => Err!(format!("cannot shift {self} by {rhs} bits")),
+		}
+	}
+}
+
+impl Neg for Value2 {
+	type Output = io::Result<Self>;
+
+	fn neg(self) -> Self::Output {
+		match self {
+			Self::Int32(v) => Ok(Self::Int32(v.value().wrapping_neg().into())),
+			Self::Int64(v) => Ok(Self::Int64(v.value().wrapping_neg().into())),
+			Self::Float32(v) => Ok(Self::Float32((-v.value()).into())),
+			Self::Float64(v) => Ok(Self::Float64((-v.value()).into())),
+
+			other => invalidUnary("-", &other),
+		}
+	}
+}
+
+impl Not for Value2 {
+	type Output = io::Result<Self>;
+
+	fn not(self) -> Self::Output {
+		match self {
+			Self::Int32(v) => Ok(Self::Int32((!v.value()).into())),
+			Self::Int64(v) => Ok(Self::Int64((!v.value()).into())),
+			Self::True => Ok(Self::False),
+			Self::False => Ok(Self::True),
+
+			other => invalidUnary("~", &other),
 		}
 	}
 }
@@ -337,271 +537,3 @@ impl TryFrom<TokenValue> for Value2 {
 		}
 	}
 }
-
-#[derive(Debug)]
-pub enum Value {
-	Null,
-	True,
-	False,
-	Int32(i32),
-	Int64(i64),
-	Float(f64),
-
-	Char(char),
-	Str(String),
-}
-
-impl ToString for Value {
-	fn to_string(&self) -> String {
-		match self {
-			Self::Null => String::from("null"),
-			Self::True => String::from("true"),
-			Self::False => String::from("false"),
-			Self::Int32(v) => v.to_string(),
-			Self::Int64(v) => v.to_string(),
-			Self::Float(v) => v.to_string(),
-			Self::Char(v) => v.to_string(),
-			Self::Str(v) => v.to_string(),
-
-			#[allow(unreachable_patterns)]
-			other => todo!("{other:?}.to_string()"),
-		}
-	}
-}
-
-impl ToBytes for Value {
-	fn bytes(&self) -> Vec<u8> {
-		let mut bytes = Vec::new();
-
-		match &self {
-			Self::Null => bytes.push(VALUE_NULL),
-			Self::True => bytes.push(VALUE_TRUE),
-			Self::False => bytes.push(VALUE_FALSE),
-			Self::Int32(v) => {
-				bytes.push(VALUE_INT32);
-				bytes.extend(v.to_le_bytes());
-			}
-			Self::Int64(v) => {
-				bytes.push(VALUE_INT64);
-				bytes.extend(v.to_le_bytes());
-			}
-			Self::Float(v) => {
-				bytes.push(VALUE_FLOAT64);
-				bytes.extend(v.to_le_bytes());
-			}
-			Self::Char(c) => {
-				bytes.push(VALUE_CHAR);
-				bytes.push(*c as u8);
-			}
-			Self::Str(s) => {
-				bytes.push(VALUE_STR);
-				bytes.extend((s.len() as u16).to_le_bytes());
-				bytes.extend(s.chars().map(|c| c as u8));
-			}
-			#[allow(unreachable_patterns)]
-			other => todo!("{other:?}"),
-		}
-
-		bytes
-	}
-}
-
-impl FromBytes for Value {
-	fn fromBytes(bytes: &mut Vec<u8>) -> Self {
-		match bytes.remove(0) {
-			VALUE_NULL => Self::Null,
-			VALUE_TRUE => Self::True,
-			VALUE_FALSE => Self::False,
-			VALUE_INT32 => {
-				let mut valBytes = [0; 4];
-				(0..4).for_each(|idx| valBytes[idx] = bytes.remove(0));
-
-				Self::Int32(i32::from_le_bytes(valBytes))
-			}
-			VALUE_INT64 => {
-				let mut valBytes = [0; 8];
-				(0..8).for_each(|idx| valBytes[idx] = bytes.remove(0));
-
-				Self::Int64(i64::from_le_bytes(valBytes))
-			}
-			VALUE_FLOAT64 => {
-				let mut valBytes = [0; 8];
-				(0..8).for_each(|idx| valBytes[idx] = bytes.remove(0));
-
-				Self::Float(f64::from_le_bytes(valBytes))
-			}
-			VALUE_CHAR => Self::Char(bytes.remove(0) as char),
-			VALUE_STR => {
-				let strSizeBytes = [bytes.remove(0), bytes.remove(0)];
-				let strSize = u16::from_le_bytes(strSizeBytes) as usize;
-
-				let mut strBytes = Vec::new();
-
-				(0..strSize).for_each(|_| strBytes.push(bytes.remove(0)));
-
-				let s = String::from_utf8(strBytes).expect("failed to read string");
-
-				Self::Str(s)
-			}
-			#[allow(unreachable_patterns)]
-			other => todo!("{other:?}"),
-		}
-	}
-}
-
-impl Add for Value {
-	type Output = io::Result<Self>;
-
-	fn add(self, rhs: Self) -> Self::Output {
-		match (self, rhs) {
-			(Self::Int32(a), Self::Int32(b)) => Ok(Self::Int32(a + b)),
-			(Self::Int32(a), Self::Int64(b)) => Ok(Self::Int64(a as i64 + b)),
-			(Self::Int32(a), Self::Float(b)) => Ok(Self::Float(a as f64 + b)),
-			(Self::Int32(a), Self::True) => Ok(Self::Int32(a + 1)),
-			(Self::Int32(a), Self::False) => Ok(Self::Int32(a)),
-
-			(Self::Int64(a), Self::Int32(b)) => Ok(Self::Int64(a + b as i64)),
-			(Self::Int64(a), Self::Int64(b)) => Ok(Self::Int64(a + b)),
-			(Self::Int64(a), Self::Float(b)) => Ok(Self::Float(a as f64 + b)),
-			(Self::Int64(a), Self::True) => Ok(Self::Int64(a + 1)),
-			(Self::Int64(a), Self::False) => Ok(Self::Int64(a)),
-
-			(Self::Float(a), Self::Float(b)) => Ok(Self::Float(a + b)),
-			(Self::Float(a), Self::Int32(b)) => Ok(Self::Float(a + b as f64)),
-			(Self::Float(a), Self::Int64(b)) => Ok(Self::Float(a + b as f64)),
-			(Self::Float(a), Self::True) => Ok(Self::Float(a + 1.0)),
-			(Self::Float(a), Self::False) => Ok(Self::Float(a)),
-
-			(Self::Str(a), Self::Int32(b)) => Ok(Self::Str(a + b.to_string().as_str())),
-			(Self::Str(a), Self::Int64(b)) => Ok(Self::Str(a + b.to_string().as_str())),
-			(Self::Str(a), Self::Float(b)) => Ok(Self::Str(a + b.to_string().as_str())),
-			(Self::Str(a), Self::True) => Ok(Self::Str(a + "true")),
-			(Self::Str(a), Self::False) => Ok(Self::Str(a + "false")),
-			(Self::Str(a), Self::Char(b)) => Ok(Self::Str(a + b.to_string().as_str())),
-			(Self::Str(a), Self::Str(b)) => Ok(Self::Str(a + b.as_str())),
-
-			(otherA, otherB) => Err!(format!("invalid operation {otherA:?} + {otherB:?}")),
-		}
-	}
-}
-
-impl Sub for Value {
-	type Output = io::Result<Self>;
-
-	fn sub(self, rhs: Self) -> Self::Output {
-		match (self, rhs) {
-			(Self::Int32(a), Self::Int32(b)) => Ok(Self::Int32(a - b)),
-			(Self::Int32(a), Self::Int64(b)) => Ok(Self::Int64(a as i64 - b)),
-			(Self::Int32(a), Self::Float(b)) => Ok(Self::Float(a as f64 - b)),
-			(Self::Int32(a), Self::True) => Ok(Self::Int32(a - 1)),
-			(Self::Int32(a), Self::False) => Ok(Self::Int32(a)),
-
-			(Self::Int64(a), Self::Int32(b)) => Ok(Self::Int64(a - b as i64)),
-			(Self::Int64(a), Self::Int64(b)) => Ok(Self::Int64(a - b)),
-			(Self::Int64(a), Self::Float(b)) => Ok(Self::Float(a as f64 - b)),
-			(Self::Int64(a), Self::True) => Ok(Self::Int64(a - 1)),
-			(Self::Int64(a), Self::False) => Ok(Self::Int64(a)),
-
-			(Self::Float(a), Self::Float(b)) => Ok(Self::Float(a - b)),
-			(Self::Float(a), Self::Int32(b)) => Ok(Self::Float(a - b as f64)),
-			(Self::Float(a), Self::Int64(b)) => Ok(Self::Float(a - b as f64)),
-			(Self::Float(a), Self::True) => Ok(Self::Float(a - 1.0)),
-			(Self::Float(a), Self::False) => Ok(Self::Float(a)),
-
-			(otherA, otherB) => Err!(format!("invalid operation {otherA:?} - {otherB:?}")),
-		}
-	}
-}
-
-impl Mul for Value {
-	type Output = io::Result<Self>;
-
-	fn mul(self, rhs: Self) -> Self::Output {
-		match (self, rhs) {
-			(Self::Int32(a), Self::Int32(b)) => Ok(Self::Int32(a * b)),
-			(Self::Int32(a), Self::Int64(b)) => Ok(Self::Int64(a as i64 * b)),
-			(Self::Int32(a), Self::Float(b)) => Ok(Self::Float(a as f64 * b)),
-			(Self::Int32(a), Self::True) => Ok(Self::Int32(a)),
-			(Self::Int32(_), Self::False) => Ok(Self::Int32(0)),
-
-			(Self::Int64(a), Self::Int32(b)) => Ok(Self::Int64(a * b as i64)),
-			(Self::Int64(a), Self::Int64(b)) => Ok(Self::Int64(a * b)),
-			(Self::Int64(a), Self::Float(b)) => Ok(Self::Float(a as f64 * b)),
-			(Self::Int64(a), Self::True) => Ok(Self::Int64(a)),
-			(Self::Int64(_), Self::False) => Ok(Self::Int64(0)),
-
-			(Self::Float(a), Self::Float(b)) => Ok(Self::Float(a * b)),
-			(Self::Float(a), Self::Int32(b)) => Ok(Self::Float(a * b as f64)),
-			(Self::Float(a), Self::Int64(b)) => Ok(Self::Float(a * b as f64)),
-			(Self::Float(a), Self::True) => Ok(Self::Float(a)),
-			(Self::Float(_), Self::False) => Ok(Self::Float(0.0)),
-
-			(otherA, otherB) => Err!(format!("invalid operation {otherA:?} * {otherB:?}")),
-		}
-	}
-}
-
-impl Div for Value {
-	type Output = io::Result<Self>;
-
-	fn div(self, rhs: Self) -> Self::Output {
-		match (self, rhs) {
-			(Self::Int32(_) | Self::Int64(_) | Self::Float(_), Self::Int32(0) | Self::Int64(0) | Self::False) => {
-				Err!("cannot divide by 0")
-			}
-			(Self::Int32(_) | Self::Int64(_) | Self::Float(_), Self::Float(v)) if v == 0.0 => Err!("cannot divide by 0"),
-			(Self::Int32(a), Self::Int32(b)) => Ok(Self::Int32(a / b)),
-			(Self::Int32(a), Self::Int64(b)) => Ok(Self::Int64(a as i64 / b)),
-			(Self::Int32(a), Self::Float(b)) => Ok(Self::Float(a as f64 / b)),
-			(Self::Int32(a), Self::True) => Ok(Self::Int32(a)),
-
-			(Self::Int64(a), Self::Int32(b)) => Ok(Self::Int64(a / b as i64)),
-			(Self::Int64(a), Self::Int64(b)) => Ok(Self::Int64(a / b)),
-			(Self::Int64(a), Self::Float(b)) => Ok(Self::Float(a as f64 / b)),
-			(Self::Int64(a), Self::True) => Ok(Self::Int64(a)),
-
-			(Self::Float(a), Self::Float(b)) => Ok(Self::Float(a / b)),
-			(Self::Float(a), Self::Int32(b)) => Ok(Self::Float(a / b as f64)),
-			(Self::Float(a), Self::Int64(b)) => Ok(Self::Float(a / b as f64)),
-			(Self::Float(a), Self::True) => Ok(Self::Float(a)),
-
-			(otherA, otherB) => Err!(format!("invalid operation {otherA:?} / {otherB:?}")),
-		}
-	}
-}
-
-impl From<bool> for Value {
-	fn from(b: bool) -> Self {
-		if b { Self::True } else { Self::False }
-	}
-}
-
-impl From<i32> for Value {
-	fn from(v: i32) -> Self {
-		Self::Int32(v)
-	}
-}
-
-impl From<i64> for Value {
-	fn from(v: i64) -> Self {
-		Self::Int64(v)
-	}
-}
-
-impl From<f64> for Value {
-	fn from(v: f64) -> Self {
-		Self::Float(v)
-	}
-}
-
-impl From<char> for Value {
-	fn from(c: char) -> Self {
-		Self::Char(c)
-	}
-}
-
-impl From<String> for Value {
-	fn from(s: String) -> Self {
-		Self::Str(s)
-	}
-}
@@ -1,6 +1,7 @@
 pub mod int {
 	use std::{
 		convert,
+		fmt,
 		ops::{Add, Div, Mul, Sub},
 	};
 
@@ -27,15 +28,15 @@ pub mod int {
 		}
 	}
 
-	impl<T: IntT> ToString for Int<T> {
-		fn to_string(&self) -> String {
-			self.value.to_string()
+	impl<T: IntT> fmt::Display for Int<T> {
+		fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+			write!(f, "{}", self.value)
 		}
 	}
 
 	impl<I: IntT + convert::Into<I>> From<I> for Int<I> {
 		fn from(i: I) -> Self {
-			Self::new(i.into())
+			Self::new(i)
 		}
 	}
 
@@ -47,7 +48,7 @@ pub mod int {
 		type Output = Self;
 
 		fn add(self, rhs: A) -> Self::Output {
-			Self::Output::new(self.value + rhs.into())
+			Self::Output::new(self.value.wrapping_add(rhs.into()))
 		}
 	}
 
@@ -59,7 +60,7 @@ pub mod int {
 		type Output = Self;
 
 		fn sub(self, rhs: S) -> Self::Output {
-			Self::new(self.value + rhs.into())
+			Self::new(self.value.wrapping_sub(rhs.into()))
 		}
 	}
 
@@ -71,7 +72,7 @@ pub mod int {
 		type Output = Self;
 
 		fn mul(self, rhs: M) -> Self::Output {
-			Self::new(self.value + rhs.into())
+			Self::new(self.value.wrapping_mul(rhs.into()))
 		}
 	}
 
@@ -83,7 +84,7 @@ pub mod int {
 		type Output = Self;
 
 		fn div(self, rhs: D) -> Self::Output {
-			Self::new(self.value + rhs.into())
+			Self::new(self.value.wrapping_div(rhs.into()))
 		}
 	}
 
@@ -91,7 +92,7 @@ pub mod int {
 		type Output = Int64;
 
 		fn add(self, rhs: Int64) -> Self::Output {
-			Self::Output::new(self.value as i64 + rhs.value)
+			Self::Output::new((self.value as i64).wrapping_add(rhs.value))
 		}
 	}
 
@@ -99,7 +100,7 @@ pub mod int {
 		type Output = Int64;
 
 		fn sub(self, rhs: Int64) -> Self::Output {
-			Self::Output::new(self.value as i64 + rhs.value)
+			Self::Output::new((self.value as i64).wrapping_sub(rhs.value))
 		}
 	}
 
@@ -107,7 +108,7 @@ pub mod int {
 		type Output = Int64;
 
 		fn mul(self, rhs: Int64) -> Self::Output {
-			Self::Output::new(self.value as i64 * rhs.value)
+			Self::Output::new((self.value as i64).wrapping_mul(rhs.value))
 		}
 	}
 
@@ -115,7 +116,7 @@ pub mod int {
 		type Output = Int64;
 
 		fn div(self, rhs: Int64) -> Self::Output {
-			Self::Output::new(self.value as i64 / rhs.value)
+			Self::Output::new((self.value as i64).wrapping_div(rhs.value))
 		}
 	}
 
@@ -140,6 +141,7 @@ pub mod int {
 pub mod float {
 	use std::{
 		convert,
+		fmt,
 		ops::{Add, Div, Mul, Sub},
 	};
 
@@ -166,15 +168,15 @@ pub mod float {
 		}
 	}
 
-	impl<F: FloatT> ToString for Float<F> {
-		fn to_string(&self) -> String {
-			self.value.to_string()
+	impl<F: FloatT> fmt::Display for Float<F> {
+		fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+			write!(f, "{}", self.value)
 		}
 	}
 
 	impl<F: FloatT + convert::Into<F>> From<F> for Float<F> {
 		fn from(f: F) -> Self {
-			Self::new(f.into())
+			Self::new(f)
 		}
 	}
 	impl From<Float32> for Float64 {
@@ -193,7 +195,7 @@ pub mod float {
 	}
 	impl ToBytes for Float64 {
 		fn bytes(&self) -> Vec<u8> {
-			let mut bytes = Vec::with_capacity(0);
+			let mut bytes = Vec::with_capacity(9);
 			bytes.push(VALUE_FLOAT64);
 			bytes.extend(self.value.to_le_bytes());
 			bytes
@@ -220,7 +222,7 @@ pub mod float {
 		type Output = Self;
 
 		fn sub(self, rhs: S) -> Self::Output {
-			Self::new(self.value + rhs.into())
+			Self::new(self.value - rhs.into())
 		}
 	}
 
@@ -232,7 +234,7 @@ pub mod float {
 		type Output = Self;
 
 		fn mul(self, rhs: M) -> Self::Output {
-			Self::new(self.value + rhs.into())
+			Self::new(self.value * rhs.into())
 		}
 	}
 
@@ -244,7 +246,7 @@ pub mod float {
 		type Output = Self;
 
 		fn div(self, rhs: D) -> Self::Output {
-			Self::new(self.value + rhs.into())
+			Self::new(self.value / rhs.into())
 		}
 	}
 
@@ -1,4 +1,4 @@
-use std::{ops::Add, string::String as StdString};
+use std::{fmt, ops::Add, string::String as StdString};
 
 use super::VALUE_STR;
 use crate::bytes::ToBytes;
@@ -17,14 +17,22 @@ impl String {
 		self.value.clone()
 	}
 
+	pub fn asStr(&self) -> &str {
+		&self.value
+	}
+
 	pub fn len(&self) -> usize {
 		self.value.len()
 	}
+
+	pub fn is_empty(&self) -> bool {
+		self.value.is_empty()
+	}
 }
 
-impl ToString for String {
-	fn to_string(&self) -> StdString {
-		self.value.to_string()
+impl fmt::Display for String {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.value)
 	}
 }
 
@@ -34,7 +42,7 @@ impl ToBytes for String {
 
 		bytes.push(VALUE_STR);
 		bytes.extend((self.value.len() as u16).to_le_bytes());
-		bytes.extend(self.value.chars().map(|c| c as u8));
+		bytes.extend(self.value.as_bytes());
 
 		bytes
 	}
@@ -0,0 +1,462 @@
+use std::{
+	collections::{BTreeSet, HashMap},
+	error,
+	fmt,
+	rc::Rc,
+};
+
+use crate::{
+	bytes::{FromBytes, Reader},
+	instruction::{Capture, Instruction, Slot},
+	value::function::Function,
+	ValueType,
+};
+
+/// Something wrong with a program, found without running it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+	offset:  usize,
+	message: String,
+}
+
+impl Problem {
+	fn new(offset: usize, message: impl Into<String>) -> Self {
+		Self {
+			offset,
+			message: message.into(),
+		}
+	}
+
+	/// Byte offset of the offending instruction in the code.
+	pub fn offset(&self) -> usize {
+		self.offset
+	}
+
+	pub fn message(&self) -> &str {
+		&self.message
+	}
+}
+
+impl fmt::Display for Problem {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} at offset {}", self.message, self.offset)
+	}
+}
+
+impl error::Error for Problem {}
+
+/// Check that `code` decodes, that every jump and function entry lands on an instruction, that no path pops
+/// more values than it pushed or reaches an instruction with a different stack depth than another path, and
+/// that the program ends with `HALT`. Upvalues may only be accessed in the body of a closure, and only the ones
+/// it captured. A local captured by a closure may only leave the stack through `CLOSE_UPVALUE` or `RETURN`.
+/// `constants`, `strings` and `functions` are the program's constant pool, string table and function table, every
+/// index into them has to exist.
+pub fn verify(
+	code: &[u8],
+	constants: &[ValueType],
+	strings: &[String],
+	functions: &[Rc<Function>],
+) -> Result<(), Vec<Problem>> {
+	let mut verifier = Verifier {
+		constants,
+		strings,
+		functions,
+		..Default::default()
+	};
+
+	verifier.decode(code);
+	verifier.checkTargets();
+	verifier.checkStack();
+
+	// instructions checked again report their problems again
+	let mut problems: Vec<Problem> = Vec::new();
+	for problem in verifier.problems {
+		if !problems.contains(&problem) {
+			problems.push(problem);
+		}
+	}
+
+	if problems.is_empty() {
+		Ok(())
+	} else {
+		problems.sort_by_key(Problem::offset);
+		Err(problems)
+	}
+}
+
+struct Decoded {
+	offset: usize,
+	end:    usize,
+	instr:  Instruction,
+}
+
+#[derive(Default)]
+struct Verifier<'a> {
+	constants: &'a [ValueType],
+	strings:   &'a [String],
+	functions: &'a [Rc<Function>],
+	instrs:    Vec<Decoded>,
+	problems:  Vec<Problem>,
+	/// Stack depth, relative to the frame's base, on entry to each instruction, once a path has reached it.
+	depths:    Vec<Option<usize>>,
+	/// Number of upvalues the code running each instruction captured, `None` outside of a closure.
+	captures:  Vec<Option<usize>>,
+	/// Local slots captured by a closure on some path to each instruction, and not closed yet.
+	open:      Vec<BTreeSet<usize>>,
+	/// Instructions that were reached but not checked yet.
+	pending:   Vec<usize>,
+}
+
+impl Verifier<'_> {
+	fn decode(&mut self, code: &[u8]) {
+		let mut reader = Reader::at(code, 0);
+
+		while reader.offset() < code.len() {
+			let offset = reader.offset();
+
+			match Instruction::fromBytes(&mut reader) {
+				Ok(instr) => self.instrs.push(Decoded {
+					offset,
+					end: reader.offset(),
+					instr,
+				}),
+				Err(e) => {
+					// nothing after an undecodable instruction can be trusted
+					self.problems.push(Problem::new(e.offset(), e.kind().to_string()));
+					return;
+				}
+			}
+		}
+
+		match self.instrs.last() {
+			Some(Decoded {
+				instr: Instruction::Halt,
+				..
+			}) => {}
+			Some(last) => self
+				.problems
+				.push(Problem::new(last.offset, "program does not end with HALT")),
+			None => self.problems.push(Problem::new(0, "program does not end with HALT")),
+		}
+	}
+
+	fn checkTargets(&mut self) {
+		for decoded in self.instrs.iter() {
+			if let Some(target) = jumpTarget(decoded) {
+				if self.index(target).is_none() {
+					self.problems.push(Problem::new(
+						decoded.offset,
+						format!(
+							"{} to offset {target}, which is not the start of an instruction",
+							decoded.instr.mnemonic()
+						),
+					));
+				}
+			}
+
+			if let Instruction::PushConst(index) = decoded.instr {
+				if self.constants.get(index as usize).is_none() {
+					self.problems.push(Problem::new(
+						decoded.offset,
+						format!("constant {index} does not exist, the pool only has {}", self.constants.len()),
+					));
+				}
+			}
+
+			if let Instruction::PushFunction(index) = decoded.instr {
+				if self.functions.get(index as usize).is_none() {
+					self.problems.push(Problem::new(
+						decoded.offset,
+						format!(
+							"function {index} does not exist, the table only has {}",
+							self.functions.len()
+						),
+					));
+				}
+			}
+
+			if let Some(index) = decoded.instr.nameIndex() {
+				if self.strings.get(index as usize).is_none() {
+					self.problems.push(Problem::new(
+						decoded.offset,
+						format!("string {index} does not exist, the table only has {}", self.strings.len()),
+					));
+				}
+			}
+
+			if let Some(function) = self.function(&decoded.instr) {
+				if self.index(function.entry() as i64).is_none() {
+					self.problems.push(Problem::new(
+						decoded.offset,
+						format!(
+							"{function} starts at offset {}, which is not the start of an instruction",
+							function.entry()
+						),
+					));
+				}
+			}
+		}
+	}
+
+	/// Follow every path through the program and every function, starting with an empty stack for the program
+	/// and with the callee and its arguments for a function.
+	fn checkStack(&mut self) {
+		self.depths = vec![None; self.instrs.len()];
+		self.captures = vec![None; self.instrs.len()];
+		self.open = vec![BTreeSet::new(); self.instrs.len()];
+
+		if !self.instrs.is_empty() {
+			self.reach(0, 0, None, BTreeSet::new());
+		}
+
+		for (idx, (depth, captures)) in self.entries() {
+			self.reach(idx, depth, captures, BTreeSet::new());
+		}
+
+		while let Some(idx) = self.pending.pop() {
+			self.step(idx);
+		}
+	}
+
+	/// Index, stack depth and captured upvalues on entry of every function. A function only runs as a closure if
+	/// every instruction pushing it is followed by `CLOSURE`, and then captures as many upvalues as the smallest
+	/// of them.
+	fn entries(&self) -> HashMap<usize, (usize, Option<usize>)> {
+		let mut entries = HashMap::new();
+
+		for (idx, decoded) in self.instrs.iter().enumerate() {
+			let Some(function) = self.function(&decoded.instr) else {
+				continue;
+			};
+			let Some(entry) = self.index(function.entry() as i64) else {
+				continue;
+			};
+
+			let captures = match self.instrs.get(idx + 1).map(|next| &next.instr) {
+				Some(Instruction::Closure(captures)) => Some(captures.len()),
+				_ => None,
+			};
+
+			entries
+				.entry(entry)
+				.and_modify(|entry: &mut (usize, Option<usize>)| entry.1 = entry.1.min(captures))
+				.or_insert((usize::from(function.arity()) + 1, captures));
+		}
+
+		entries
+	}
+
+	fn step(&mut self, idx: usize) {
+		let depth = self.depths[idx].unwrap();
+		let captured = self.captures[idx];
+		let decoded = &self.instrs[idx];
+		let (pops, pushes) = stackEffect(&decoded.instr);
+
+		if depth < pops {
+			let problem = Problem::new(
+				decoded.offset,
+				format!(
+					"stack underflow, {} needs {pops} values but the stack only has {depth}",
+					decoded.instr.mnemonic()
+				),
+			);
+			self.problems.push(problem);
+			return;
+		}
+
+		let slots: Vec<Slot> = match &decoded.instr {
+			Instruction::LoadLocal(slot) | Instruction::StoreLocal(slot) => vec![*slot],
+			Instruction::Closure(captures) => captures
+				.iter()
+				.filter(|capture| capture.isLocal)
+				.map(|Capture { index, .. }| *index)
+				.collect(),
+			_ => Vec::new(),
+		};
+
+		for slot in slots {
+			if usize::from(slot) >= depth {
+				let problem = Problem::new(
+					decoded.offset,
+					format!("local slot {slot} is out of range, the frame only has {depth} values"),
+				);
+				self.problems.push(problem);
+			}
+		}
+
+		let upvalues: Vec<Slot> = match &decoded.instr {
+			Instruction::LoadUpvalue(idx) | Instruction::StoreUpvalue(idx) => vec![*idx],
+			Instruction::Closure(captures) => captures
+				.iter()
+				.filter(|capture| !capture.isLocal)
+				.map(|Capture { index, .. }| *index)
+				.collect(),
+			_ => Vec::new(),
+		};
+
+		for upvalue in upvalues {
+			let message = match captured {
+				None => format!("upvalue {upvalue} is accessed outside of a closure"),
+				Some(captured) if usize::from(upvalue) >= captured => {
+					format!("upvalue {upvalue} is out of range, the closure only captures {captured}")
+				}
+				Some(_) => continue,
+			};
+			self.problems.push(Problem::new(decoded.offset, message));
+		}
+
+		let kept = depth - pops;
+		let mut open = self.open[idx].clone();
+
+		if !matches!(decoded.instr, Instruction::CloseUpvalue | Instruction::Return) {
+			for slot in open.range(kept..) {
+				let problem = Problem::new(
+					decoded.offset,
+					format!(
+						"{} pops local slot {slot}, which is captured by a closure, without closing it",
+						decoded.instr.mnemonic()
+					),
+				);
+				self.problems.push(problem);
+			}
+		}
+
+		open.retain(|slot| *slot < kept);
+		if let Instruction::Closure(captures) = &decoded.instr {
+			open.extend(captures.iter().filter(|capture| capture.isLocal).map(|capture| usize::from(capture.index)));
+		}
+
+		let next = kept + pushes;
+		let target = jumpTarget(decoded).and_then(|target| self.index(target));
+		let targetDepth = match decoded.instr {
+			// the value is only popped if the jump is not taken
+			Instruction::JumpIfFalseOrPop(_) | Instruction::JumpIfTrueOrPop(_) => depth,
+			_ => next,
+		};
+		let fallsThrough = !matches!(decoded.instr, Instruction::Halt | Instruction::Return | Instruction::Jump(_));
+
+		if let Some(target) = target {
+			// a jump that doesn't pop keeps every local open
+			let open = if targetDepth == depth { self.open[idx].clone() } else { open.clone() };
+			self.reach(target, targetDepth, captured, open);
+		}
+
+		if fallsThrough && idx + 1 < self.instrs.len() {
+			self.reach(idx + 1, next, captured, open);
+		}
+	}
+
+	fn reach(&mut self, idx: usize, depth: usize, captures: Option<usize>, open: BTreeSet<usize>) {
+		// a local captured on any path has to be closed on every path after it
+		if self.depths[idx].is_some() && !open.is_subset(&self.open[idx]) {
+			self.open[idx].extend(open);
+			self.pending.push(idx);
+		} else if self.depths[idx].is_none() {
+			self.open[idx] = open;
+		}
+
+		if self.depths[idx].is_some() && self.captures[idx] != captures {
+			let problem = Problem::new(
+				self.instrs[idx].offset,
+				"instruction is reached from functions capturing different upvalues",
+			);
+			self.problems.push(problem);
+		}
+
+		match self.depths[idx] {
+			None => {
+				self.depths[idx] = Some(depth);
+				self.captures[idx] = captures;
+				self.pending.push(idx);
+			}
+			Some(known) if known != depth => {
+				let problem = Problem::new(
+					self.instrs[idx].offset,
+					format!("stack depth is {known} on one path to this instruction but {depth} on another"),
+				);
+				self.problems.push(problem);
+			}
+			Some(_) => {}
+		}
+	}
+
+	/// The function pushed by `instr`, if it pushes one.
+	fn function(&self, instr: &Instruction) -> Option<&Function> {
+		match instr {
+			Instruction::PushFunction(index) => self.functions.get(*index as usize).map(|function| &**function),
+			_ => None,
+		}
+	}
+
+	/// Index of the instruction starting at `offset`.
+	fn index(&self, offset: i64) -> Option<usize> {
+		let offset = usize::try_from(offset).ok()?;
+		self.instrs.binary_search_by_key(&offset, |decoded| decoded.offset).ok()
+	}
+}
+
+fn jumpTarget(decoded: &Decoded) -> Option<i64> {
+	match decoded.instr {
+		Instruction::Jump(offset)
+		| Instruction::JumpIfFalse(offset)
+		| Instruction::JumpIfTrue(offset)
+		| Instruction::JumpIfFalseOrPop(offset)
+		| Instruction::JumpIfTrueOrPop(offset) => Some(decoded.end as i64 + i64::from(offset)),
+		_ => None,
+	}
+}
+
+/// How many values an instruction pops, and how many it pushes afterwards.
+fn stackEffect(instr: &Instruction) -> (usize, usize) {
+	match instr {
+		Instruction::Halt | Instruction::Jump(_) => (0, 0),
+		Instruction::Push(_)
+		| Instruction::PushConst(_)
+		| Instruction::PushFunction(_)
+		| Instruction::LoadLocal(_)
+		| Instruction::LoadGlobal(_)
+		| Instruction::LoadUpvalue(_)
+		| Instruction::ReadLine
+		| Instruction::ReadChar
+		| Instruction::Class(_) => (0, 1),
+		Instruction::Pop
+		| Instruction::Print
+		| Instruction::DefineGlobal(_)
+		| Instruction::CloseUpvalue
+		| Instruction::JumpIfFalse(_)
+		| Instruction::JumpIfTrue(_)
+		| Instruction::JumpIfFalseOrPop(_)
+		| Instruction::JumpIfTrueOrPop(_)
+		| Instruction::Return => (1, 0),
+		Instruction::Neg
+		| Instruction::Not
+		| Instruction::BitNot
+		| Instruction::StoreLocal(_)
+		| Instruction::StoreGlobal(_)
+		| Instruction::StoreUpvalue(_)
+		| Instruction::Closure(_)
+		| Instruction::GetProperty(_) => (1, 1),
+		Instruction::Add
+		| Instruction::Sub
+		| Instruction::Mul
+		| Instruction::Div
+		| Instruction::Mod
+		| Instruction::Pow
+		| Instruction::BitAnd
+		| Instruction::BitOr
+		| Instruction::BitXor
+		| Instruction::Shl
+		| Instruction::Shr
+		| Instruction::Equal
+		| Instruction::NotEqual
+		| Instruction::Less
+		| Instruction::LessEqual
+		| Instruction::Greater
+		| Instruction::GreaterEqual
+		| Instruction::Inherit
+		| Instruction::Method(_)
+		| Instruction::SetProperty(_)
+		| Instruction::GetSuper(_) => (2, 1),
+		Instruction::Call(argc) => (usize::from(*argc) + 1, 1),
+	}
+}
@@ -1,84 +1,707 @@
 use std::{
-	io::Result,
-	ops::{Add, Div, Mul, Sub},
+	cell::RefCell,
+	cmp::Ordering,
+	collections::HashMap,
+	io::{self, BufRead, Write},
+	iter,
+	ops::{Add, BitAnd, BitOr, BitXor, Div, Mul, Neg, Not, Rem, Shl, Shr, Sub},
+	rc::Rc,
+	sync::Arc,
 };
 
 use crate::{
+	assembler::Assembled,
 	ast::AST,
-	bytes::{FromBytes, ToBytes},
-	instruction::{Instruction, Instructions},
+	bytes::{DecodeError, DecodeErrorKind, FromBytes, Reader, ToBytes},
+	container::Container,
+	debuginfo::DebugInfo,
+	diagnostic::Diagnostics,
+	disassembler::Disassembler,
+	instruction::{Instruction, Instructions, Offset, Slot, StringIndex},
+	runtime::{CallSite, RuntimeError, RuntimeErrorKind},
+	span::Span,
+	value::{
+		class::{BoundMethod, Class, Instance},
+		closure::{Closure, Upvalue},
+		function::Function,
+	},
+	verifier::{self, Problem},
 	Err,
 	ValueType,
-	MAGIC_NUMBER,
 };
 
-macro_rules! exit {
-	($code:expr) => {
-		std::process::exit($code)
-	};
-	($code:expr, $msg:expr) => {{
-		eprintln!($msg);
-		std::process::exit($code)
-	}};
-}
-
 type Stack = Vec<ValueType>;
 
+/// Maximum depth of nested calls before the VM gives up with a stack overflow.
+pub const MAX_FRAMES: usize = 1024;
+/// Name of the top-level code in backtraces.
+pub const SCRIPT: &str = "<script>";
+
+/// A function call in progress.
+struct Frame {
+	function:   Rc<Function>,
+	/// The closure being run, if the function captures any variables.
+	closure:    Option<Rc<Closure>>,
+	/// Index of the called function on the stack, locals are addressed relative to it.
+	base:       usize,
+	/// Where to continue in the caller once the function returns.
+	returnIp:   usize,
+	/// Byte offset of the `CALL` instruction, for backtraces.
+	callOffset: usize,
+}
+
+#[derive(Default)]
 pub struct VM {
-	code:  Instructions,
-	stack: Stack,
+	/// The program, never modified so it can be shared between VMs and run again.
+	code:      Arc<[u8]>,
+	/// Values pushed by `PUSH_CONST`, decoded once when the program is loaded.
+	constants: Vec<ValueType>,
+	/// Names of globals, classes, methods and properties, referred to by their index.
+	strings:   Vec<String>,
+	/// Functions pushed by `PUSH_FUNCTION`.
+	functions: Vec<Rc<Function>>,
+	stack:     Stack,
+	globals:   HashMap<String, ValueType>,
+	frames:    Vec<Frame>,
+	/// Upvalues still pointing into the stack, ordered by stack index.
+	open:      Vec<Rc<RefCell<Upvalue>>>,
+	/// Byte offset (into `code`) of each instruction paired with the span of the source it was compiled from. Only
+	/// available when the VM was built from an `AST`.
+	spans:     Vec<(usize, Span)>,
+	/// Source locations of a program loaded from a container, only looked up to report errors.
+	debugInfo: Option<DebugInfo>,
+	/// Where `PRINT` writes to, stdout if not set.
+	output:    Option<Box<dyn Write>>,
+	/// Where `READ_LINE` and `READ_CHAR` read from, stdin if not set.
+	input:     Option<Box<dyn BufRead>>,
+	/// Name of the source file, shown in backtraces. Taken from the debug info if not set.
+	file:      Option<String>,
+	/// Byte offset of the instruction currently being executed.
+	current:   usize,
+	/// Byte offset of the next instruction to execute.
+	ip:        usize,
 }
 
 impl VM {
-	pub fn new(code: Instructions) -> Self {
+	pub fn new(code: impl Into<Arc<[u8]>>) -> Self {
 		Self {
-			code,
+			code: code.into(),
 			..Default::default()
 		}
 	}
 
-	pub fn run(&mut self) -> Result<i32> {
-		if self.code[..MAGIC_NUMBER.len()] == MAGIC_NUMBER {
-			self.code.drain(..MAGIC_NUMBER.len());
-		}
+	pub fn withConstants(mut self, constants: Vec<ValueType>) -> Self {
+		self.constants = constants;
+		self
+	}
+
+	pub fn withStrings(mut self, strings: Vec<String>) -> Self {
+		self.strings = strings;
+		self
+	}
+
+	pub fn withFunctions(mut self, functions: Vec<Rc<Function>>) -> Self {
+		self.functions = functions;
+		self
+	}
+
+	pub fn withDebugInfo(mut self, debugInfo: DebugInfo) -> Self {
+		self.debugInfo = Some(debugInfo);
+		self
+	}
+
+	/// Write printed values to `output` instead of stdout.
+	pub fn withOutput(mut self, output: impl Write + 'static) -> Self {
+		self.output = Some(Box::new(output));
+		self
+	}
+
+	/// Read input from `input` instead of stdin.
+	pub fn withInput(mut self, input: impl BufRead + 'static) -> Self {
+		self.input = Some(Box::new(input));
+		self
+	}
+
+	pub fn withFile(mut self, file: impl Into<String>) -> Self {
+		self.file = Some(file.into());
+		self
+	}
+
+	/// Run the program from the start. Globals and the stack of a previous run are discarded.
+	///
+	/// The program isn't verified first, bytecode `verify` would reject returns a `RuntimeError` when it gets to
+	/// the problem instead.
+	pub fn run(&mut self) -> Result<i32, RuntimeError> {
+		self.reset();
 
 		loop {
-			match Instruction::fromBytes(&mut self.code) {
+			if self.ip >= self.code.len() {
+				return Err(self.error(RuntimeErrorKind::MissingHalt));
+			}
+
+			self.current = self.ip;
+
+			let mut reader = Reader::at(&self.code, self.ip);
+			let instr = Instruction::fromBytes(&mut reader).map_err(|e| self.decodeError(e))?;
+			self.ip = reader.offset();
+
+			match instr {
 				Instruction::Halt => match self.stack.pop() {
 					None => return Ok(0),
 					Some(val) => match val {
 						ValueType::Int32(v) => return Ok(v.value()),
 						ValueType::Int64(v) => return Ok(v.value() as i32),
-						other => exit!(1, "cannot use {other:?} as exit code"),
+						other => return Err(self.error(RuntimeErrorKind::InvalidExitCode(other.to_string()))),
 					},
 				},
 				Instruction::Push(v) => self.push(v),
-				Instruction::Pop => drop(self.pop()),
-				Instruction::Add => self.binaryOp(Add::add),
-				Instruction::Sub => self.binaryOp(Sub::sub),
-				Instruction::Mul => self.binaryOp(Mul::mul),
-				Instruction::Div => self.binaryOp(Div::div),
+				Instruction::Pop => drop(self.pop()?),
+				Instruction::PushConst(index) => match self.constants.get(index as usize) {
+					Some(value) => self.push(value.clone()),
+					None => {
+						let kind = RuntimeErrorKind::InvalidBytecode(format!("constant {index} does not exist"));
+						return Err(self.error(kind));
+					}
+				},
+				Instruction::PushFunction(index) => match self.functions.get(index as usize) {
+					Some(function) => self.push(ValueType::Function(Rc::clone(function))),
+					None => {
+						let kind = RuntimeErrorKind::InvalidBytecode(format!("function {index} does not exist"));
+						return Err(self.error(kind));
+					}
+				},
+				Instruction::Add => self.binaryOp(Add::add)?,
+				Instruction::Sub => self.binaryOp(Sub::sub)?,
+				Instruction::Mul => self.binaryOp(Mul::mul)?,
+				Instruction::Div => self.binaryOp(Div::div)?,
+				Instruction::Mod => self.binaryOp(Rem::rem)?,
+				Instruction::Pow => self.binaryOp(ValueType::pow)?,
+				Instruction::BitAnd => self.binaryOp(BitAnd::bitand)?,
+				Instruction::BitOr => self.binaryOp(BitOr::bitor)?,
+				Instruction::BitXor => self.binaryOp(BitXor::bitxor)?,
+				Instruction::Shl => self.binaryOp(Shl::shl)?,
+				Instruction::Shr => self.binaryOp(Shr::shr)?,
+				Instruction::Neg => self.unaryOp(Neg::neg)?,
+				Instruction::Not => {
+					let val = self.pop()?;
+					self.push((!val.isTruthy()).into());
+				}
+				Instruction::BitNot => self.unaryOp(Not::not)?,
+				Instruction::Equal => {
+					let b = self.pop()?;
+					let a = self.pop()?;
+					self.push(a.equals(&b).into());
+				}
+				Instruction::NotEqual => {
+					let b = self.pop()?;
+					let a = self.pop()?;
+					self.push((!a.equals(&b)).into());
+				}
+				Instruction::Less => self.compare(Ordering::is_lt)?,
+				Instruction::LessEqual => self.compare(Ordering::is_le)?,
+				Instruction::Greater => self.compare(Ordering::is_gt)?,
+				Instruction::GreaterEqual => self.compare(Ordering::is_ge)?,
 				Instruction::Print => {
-					let val = self.pop();
-					print!("{}", val.to_string());
+					let val = self.pop()?;
+					self.print(&val)?;
+				}
+				Instruction::ReadLine => {
+					let line = self.read(readLine)?;
+					self.push(line.map_or(ValueType::Null, ValueType::from));
+				}
+				Instruction::ReadChar => {
+					let c = self.read(readChar)?;
+					self.push(c.map_or(ValueType::Null, ValueType::from));
+				}
+				Instruction::LoadLocal(slot) => self.push(self.stack[self.local(slot)?].clone()),
+				Instruction::StoreLocal(slot) => {
+					let idx = self.local(slot)?;
+					self.stack[idx] = self.peek()?.clone();
+				}
+				Instruction::DefineGlobal(index) => {
+					let name = self.name(index)?.to_string();
+					let val = self.pop()?;
+					self.globals.insert(name, val);
+				}
+				Instruction::LoadGlobal(index) => {
+					let name = self.name(index)?;
+					match self.globals.get(name).cloned() {
+						Some(val) => self.push(val),
+						None => return Err(self.error(RuntimeErrorKind::UndefinedVariable(name.to_string()))),
+					}
+				}
+				Instruction::StoreGlobal(index) => {
+					let name = self.name(index)?.to_string();
+					let val = self.peek()?.clone();
+					match self.globals.get_mut(&name) {
+						Some(global) => *global = val,
+						None => return Err(self.error(RuntimeErrorKind::UndefinedVariable(name))),
+					}
+				}
+				Instruction::LoadUpvalue(idx) => {
+					let val = match &*self.upvalue(idx)?.borrow() {
+						Upvalue::Open(slot) => self.stack.get(*slot).cloned().ok_or_else(|| self.openUpvalueError())?,
+						Upvalue::Closed(val) => val.clone(),
+					};
+					self.push(val);
+				}
+				Instruction::StoreUpvalue(idx) => {
+					let val = self.peek()?.clone();
+					let upvalue = Rc::clone(self.upvalue(idx)?);
+					let mut upvalue = upvalue.borrow_mut();
+					match &mut *upvalue {
+						Upvalue::Open(slot) => match self.stack.get_mut(*slot) {
+							Some(open) => *open = val,
+							None => return Err(self.openUpvalueError()),
+						},
+						Upvalue::Closed(closed) => *closed = val,
+					}
+				}
+				Instruction::CloseUpvalue => {
+					let top = self
+						.stack
+						.len()
+						.checked_sub(1)
+						.ok_or_else(|| self.error(RuntimeErrorKind::StackUnderflow))?;
+					self.closeUpvalues(top)?;
+					self.pop()?;
+				}
+				Instruction::Jump(offset) => self.jump(offset)?,
+				Instruction::JumpIfFalse(offset) => {
+					if !self.pop()?.isTruthy() {
+						self.jump(offset)?;
+					}
+				}
+				Instruction::JumpIfTrue(offset) => {
+					if self.pop()?.isTruthy() {
+						self.jump(offset)?;
+					}
+				}
+				Instruction::JumpIfFalseOrPop(offset) => {
+					if self.peek()?.isTruthy() {
+						self.pop()?;
+					} else {
+						self.jump(offset)?;
+					}
+				}
+				Instruction::JumpIfTrueOrPop(offset) => {
+					if self.peek()?.isTruthy() {
+						self.jump(offset)?;
+					} else {
+						self.pop()?;
+					}
+				}
+				Instruction::Call(argc) => self.call(argc)?,
+				Instruction::Return => {
+					let result = self.pop()?;
+					let Some(frame) = self.frames.pop() else {
+						let kind = RuntimeErrorKind::InvalidBytecode("RETURN outside of a function".to_string());
+						return Err(self.error(kind));
+					};
+
+					self.closeUpvalues(frame.base)?;
+					self.stack.truncate(frame.base);
+					self.push(result);
+					self.ip = frame.returnIp;
+				}
+				Instruction::Closure(captures) => {
+					let ValueType::Function(function) = self.pop()? else {
+						let kind = RuntimeErrorKind::TypeError("closures can only be made from functions".to_string());
+						return Err(self.error(kind));
+					};
+
+					let base = self.base();
+					let upvalues = captures
+						.iter()
+						.map(|capture| match capture.isLocal {
+							// a function declared in a block captures its own slot, where the closure is about to be pushed
+							true if base + usize::from(capture.index) <= self.stack.len() => {
+								Ok(self.captureUpvalue(base + usize::from(capture.index)))
+							}
+							true => {
+								let kind = format!("cannot capture local slot {}, it is out of range", capture.index);
+								Err(self.error(RuntimeErrorKind::InvalidBytecode(kind)))
+							}
+							false => self.upvalue(capture.index).map(Rc::clone),
+						})
+						.collect::<Result<_, _>>()?;
+
+					self.push(ValueType::Closure(Rc::new(Closure::new(function, upvalues))));
+				}
+				Instruction::Class(index) => {
+					let name = self.name(index)?.to_string();
+					self.push(ValueType::Class(Rc::new(Class::new(name))));
+				}
+				Instruction::Inherit => {
+					let ValueType::Class(class) = self.pop()? else {
+						return Err(self.error(RuntimeErrorKind::TypeError("only classes inherit".to_string())));
+					};
+					match self.peek()? {
+						ValueType::Class(superclass) => class.inherit(superclass),
+						other => {
+							let kind = RuntimeErrorKind::TypeError(format!("cannot inherit from {other}, it is not a class"));
+							return Err(self.error(kind));
+						}
+					}
+				}
+				Instruction::Method(index) => {
+					let name = self.name(index)?.to_string();
+					let method = self.pop()?;
+					let ValueType::Class(class) = self.peek()? else {
+						let kind = RuntimeErrorKind::TypeError("methods are only added to classes".to_string());
+						return Err(self.error(kind));
+					};
+					class.addMethod(name, method);
+				}
+				Instruction::GetProperty(index) => {
+					let name = self.name(index)?.to_string();
+					let instance = match self.pop()? {
+						ValueType::Instance(instance) => instance,
+						other => return Err(self.error(RuntimeErrorKind::TypeError(format!("{other} has no properties")))),
+					};
+
+					match instance.field(&name) {
+						Some(field) => self.push(field),
+						None => {
+							let method = self.bindMethod(instance.class(), &name, ValueType::Instance(Rc::clone(&instance)))?;
+							self.push(method);
+						}
+					}
+				}
+				Instruction::SetProperty(index) => {
+					let name = self.name(index)?.to_string();
+					let value = self.pop()?;
+					match self.pop()? {
+						ValueType::Instance(instance) => instance.setField(name, value.clone()),
+						other => return Err(self.error(RuntimeErrorKind::TypeError(format!("{other} has no properties")))),
+					}
+					self.push(value);
+				}
+				Instruction::GetSuper(index) => {
+					let name = self.name(index)?.to_string();
+					let ValueType::Class(superclass) = self.pop()? else {
+						return Err(self.error(RuntimeErrorKind::TypeError("'super' is always a class".to_string())));
+					};
+					let receiver = self.pop()?;
+					let method = self.bindMethod(&superclass, &name, receiver)?;
+					self.push(method);
 				}
-				#[allow(unreachable_patterns)]
-				other => exit!(1, "instruction not implemented: {other:?}"),
 			}
+		}
+	}
+
+	/// The program as text, see `Disassembler`.
+	pub fn disassemble(&self) -> Result<String, DecodeError> {
+		let lines = match &self.debugInfo {
+			Some(debugInfo) if self.spans.is_empty() => debugInfo.lines(),
+			_ => self.spans.iter().map(|(offset, span)| (*offset, span.line())).collect(),
+		};
+
+		Disassembler::new(&self.code)
+			.withConstants(&self.constants)
+			.withStrings(&self.strings)
+			.withFunctions(&self.functions)
+			.withLines(lines)
+			.disassemble()
+	}
+
+	/// Check the program with the verifier without running it.
+	pub fn verify(&self) -> Result<(), Vec<Problem>> {
+		verifier::verify(&self.code, &self.constants, &self.strings, &self.functions)
+	}
+
+	fn reset(&mut self) {
+		self.stack.clear();
+		self.globals.clear();
+		self.frames.clear();
+		self.open.clear();
+		self.current = 0;
+		self.ip = 0;
+	}
+
+	fn print(&mut self, value: &ValueType) -> Result<(), RuntimeError> {
+		let written = match self.output.as_mut() {
+			Some(output) => write!(output, "{value}"),
+			None => write!(io::stdout(), "{value}"),
+		};
+
+		written.map_err(|e| self.error(RuntimeErrorKind::Output(e.to_string())))
+	}
+
+	fn read<T>(&mut self, read: fn(&mut dyn BufRead) -> io::Result<Option<T>>) -> Result<Option<T>, RuntimeError> {
+		// show a prompt printed without a newline before waiting for input
+		let flushed = match self.output.as_mut() {
+			Some(output) => output.flush(),
+			None => io::stdout().flush(),
+		};
+		flushed.map_err(|e| self.error(RuntimeErrorKind::Output(e.to_string())))?;
+
+		let result = match self.input.as_mut() {
+			Some(input) => read(input),
+			None => read(&mut io::stdin().lock()),
+		};
 
-			if self.code.is_empty() {
-				return Err!("program exited without HALT instruction!");
+		result.map_err(|e| self.error(RuntimeErrorKind::Input(e.to_string())))
+	}
+
+	fn call(&mut self, argc: u8) -> Result<(), RuntimeError> {
+		let callee = match self.stack.len().checked_sub(1 + usize::from(argc)) {
+			Some(idx) => self.stack[idx].clone(),
+			None => return Err(self.error(RuntimeErrorKind::StackUnderflow)),
+		};
+		self.callValue(callee, argc)
+	}
+
+	/// Call `callee` with the top `argc` values on the stack as arguments. The slot below the arguments becomes
+	/// slot 0 of the new frame.
+	fn callValue(&mut self, callee: ValueType, argc: u8) -> Result<(), RuntimeError> {
+		let base = self.stack.len() - 1 - usize::from(argc);
+
+		match callee {
+			ValueType::Function(function) => self.callFunction(function, None, argc),
+			ValueType::Closure(closure) => self.callFunction(Rc::clone(closure.function()), Some(closure), argc),
+			ValueType::Class(class) => {
+				self.stack[base] = ValueType::Instance(Rc::new(Instance::new(Rc::clone(&class))));
+
+				match class.method("init") {
+					Some(init) => self.callValue(init, argc),
+					None if argc == 0 => Ok(()),
+					None => Err(self.error(RuntimeErrorKind::ArityMismatch {
+						callee:   class.to_string(),
+						expected: 0,
+						found:    argc,
+					})),
+				}
+			}
+			ValueType::BoundMethod(bound) => {
+				self.stack[base] = bound.receiver().clone();
+				self.callValue(bound.method().clone(), argc)
 			}
+			other => Err(self.error(RuntimeErrorKind::NotCallable(other.to_string()))),
 		}
 	}
 
-	fn binaryOp(&mut self, op: fn(ValueType, ValueType) -> Result<ValueType>) {
-		let b = self.pop();
-		let a = self.pop();
+	fn callFunction(&mut self, function: Rc<Function>, closure: Option<Rc<Closure>>, argc: u8) -> Result<(), RuntimeError> {
+		if function.arity() != argc {
+			return Err(self.error(RuntimeErrorKind::ArityMismatch {
+				callee:   function.to_string(),
+				expected: function.arity(),
+				found:    argc,
+			}));
+		}
+
+		if self.frames.len() == MAX_FRAMES {
+			return Err(self.error(RuntimeErrorKind::StackOverflow));
+		}
 
-		match op(a, b) {
-			Ok(result) => self.push(result),
-			Err(e) => exit!(1, "{e}"),
+		let entry = function.entry() as usize;
+		if entry >= self.code.len() {
+			let kind = RuntimeErrorKind::InvalidJump(format!("{function} starts outside of the program"));
+			return Err(self.error(kind));
+		}
+
+		self.frames.push(Frame {
+			function,
+			closure,
+			base: self.stack.len() - 1 - usize::from(argc),
+			returnIp: self.ip,
+			callOffset: self.current,
+		});
+		self.ip = entry;
+
+		Ok(())
+	}
+
+	/// Bind the method `name` of `class` to `receiver`.
+	fn bindMethod(&self, class: &Class, name: &str, receiver: ValueType) -> Result<ValueType, RuntimeError> {
+		match class.method(name) {
+			Some(method) => Ok(ValueType::BoundMethod(Rc::new(BoundMethod::new(receiver, method)))),
+			None => Err(self.error(RuntimeErrorKind::UndefinedProperty {
+				name:     name.to_string(),
+				receiver: receiver.to_string(),
+			})),
+		}
+	}
+
+	/// Name at `index` in the string table.
+	fn name(&self, index: StringIndex) -> Result<&str, RuntimeError> {
+		match self.strings.get(index as usize) {
+			Some(name) => Ok(name),
+			None => {
+				let kind = RuntimeErrorKind::InvalidBytecode(format!("string {index} does not exist"));
+				Err(self.error(kind))
+			}
+		}
+	}
+
+	/// Upvalue at `idx` of the closure currently being run.
+	fn upvalue(&self, idx: u16) -> Result<&Rc<RefCell<Upvalue>>, RuntimeError> {
+		let Some(closure) = self.frames.last().and_then(|frame| frame.closure.as_ref()) else {
+			let kind = RuntimeErrorKind::InvalidBytecode("upvalue accessed outside of a closure".to_string());
+			return Err(self.error(kind));
+		};
+
+		closure.upvalue(usize::from(idx)).ok_or_else(|| {
+			let kind = RuntimeErrorKind::InvalidBytecode(format!("upvalue {idx} does not exist"));
+			self.error(kind)
+		})
+	}
+
+	/// An open upvalue points past the end of the stack, which only happens if bytecode popped a captured local
+	/// without closing it.
+	fn openUpvalueError(&self) -> RuntimeError {
+		let kind = RuntimeErrorKind::InvalidBytecode("upvalue points outside of the stack".to_string());
+		self.error(kind)
+	}
+
+	/// Upvalue pointing at the stack slot `slot`, shared with every other closure capturing the same variable.
+	fn captureUpvalue(&mut self, slot: usize) -> Rc<RefCell<Upvalue>> {
+		let pos = self
+			.open
+			.partition_point(|upvalue| matches!(*upvalue.borrow(), Upvalue::Open(s) if s < slot));
+
+		if let Some(upvalue) = self.open.get(pos) {
+			if matches!(*upvalue.borrow(), Upvalue::Open(s) if s == slot) {
+				return Rc::clone(upvalue);
+			}
+		}
+
+		let upvalue = Rc::new(RefCell::new(Upvalue::Open(slot)));
+		self.open.insert(pos, Rc::clone(&upvalue));
+		upvalue
+	}
+
+	/// Move every captured variable at or above stack index `from` off the stack and into its upvalue.
+	fn closeUpvalues(&mut self, from: usize) -> Result<(), RuntimeError> {
+		let pos = self
+			.open
+			.partition_point(|upvalue| matches!(*upvalue.borrow(), Upvalue::Open(s) if s < from));
+
+		for upvalue in self.open.split_off(pos) {
+			let mut upvalue = upvalue.borrow_mut();
+			if let Upvalue::Open(slot) = *upvalue {
+				let value = self.stack.get(slot).ok_or_else(|| self.openUpvalueError())?;
+				*upvalue = Upvalue::Closed(value.clone());
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Stack index of the local in `slot` of the current call frame.
+	fn local(&self, slot: Slot) -> Result<usize, RuntimeError> {
+		let idx = self.base() + usize::from(slot);
+
+		match idx < self.stack.len() {
+			true => Ok(idx),
+			false => Err(self.error(RuntimeErrorKind::StackUnderflow)),
+		}
+	}
+
+	/// Stack index locals of the current call frame are relative to.
+	fn base(&self) -> usize {
+		self.frames.last().map_or(0, |frame| frame.base)
+	}
+
+	fn jump(&mut self, offset: Offset) -> Result<(), RuntimeError> {
+		match self.ip.checked_add_signed(offset as isize) {
+			Some(target) if target < self.code.len() => {
+				self.ip = target;
+				Ok(())
+			}
+			_ => Err(self.error(RuntimeErrorKind::InvalidJump(format!(
+				"jump by {offset} bytes leaves the program"
+			)))),
+		}
+	}
+
+	fn binaryOp(&mut self, op: fn(ValueType, ValueType) -> io::Result<ValueType>) -> Result<(), RuntimeError> {
+		let b = self.pop()?;
+		let a = self.pop()?;
+		let result = op(a, b).map_err(|e| self.operationError(e))?;
+		self.push(result);
+
+		Ok(())
+	}
+
+	fn unaryOp(&mut self, op: fn(ValueType) -> io::Result<ValueType>) -> Result<(), RuntimeError> {
+		let val = self.pop()?;
+		let result = op(val).map_err(|e| self.operationError(e))?;
+		self.push(result);
+
+		Ok(())
+	}
+
+	/// Pop two operands and push whether their ordering passes `test`. Comparisons involving NaN are always false.
+	fn compare(&mut self, test: fn(Ordering) -> bool) -> Result<(), RuntimeError> {
+		let b = self.pop()?;
+		let a = self.pop()?;
+
+		let ordering = a.compare(&b).map_err(|e| self.operationError(e))?;
+		self.push(ordering.is_some_and(test).into());
+
+		Ok(())
+	}
+
+	fn decodeError(&self, e: DecodeError) -> RuntimeError {
+		let kind = match e.kind() {
+			DecodeErrorKind::UnknownTag {
+				expected: "instruction",
+				tag,
+			} => RuntimeErrorKind::UnknownInstruction(*tag),
+			_ => RuntimeErrorKind::InvalidBytecode(e.to_string()),
+		};
+
+		self.error(kind)
+	}
+
+	/// Values report a division by zero as `InvalidInput`, every other failed operation is a type error.
+	fn operationError(&self, e: io::Error) -> RuntimeError {
+		match e.kind() {
+			io::ErrorKind::InvalidInput => self.error(RuntimeErrorKind::DivisionByZero),
+			_ => self.error(RuntimeErrorKind::TypeError(e.to_string())),
+		}
+	}
+
+	/// Runtime error located at the instruction currently being executed.
+	fn error(&self, kind: RuntimeErrorKind) -> RuntimeError {
+		RuntimeError::new(kind, self.current, self.spanAt(self.current))
+			.withBacktrace(self.backtrace())
+			.withFile(self.file.as_deref().or_else(|| self.debugInfo.as_ref()?.file()))
+	}
+
+	/// Call sites of the running functions, outermost first, ending at the instruction currently being executed.
+	/// Each frame's call is attributed to the function that made it.
+	fn backtrace(&self) -> Vec<CallSite> {
+		let callers = iter::once(SCRIPT).chain(self.frames.iter().map(|frame| frame.function.name()));
+		let offsets = self
+			.frames
+			.iter()
+			.map(|frame| frame.callOffset)
+			.chain(iter::once(self.current));
+
+		callers
+			.zip(offsets)
+			.map(|(function, offset)| CallSite::new(function, offset, self.spanAt(offset).map(|span| span.line())))
+			.collect()
+	}
+
+	/// Values on the stack, bottom first. After an error, this is the stack the failed instruction left behind.
+	pub fn stack(&self) -> &[ValueType] {
+		&self.stack
+	}
+
+	/// Value of the global called `name`, if it has been defined.
+	pub fn global(&self, name: &str) -> Option<&ValueType> {
+		self.globals.get(name)
+	}
+
+	/// Span of the source the instruction at `offset` was compiled from. With only debug info, the span covers
+	/// just the line and column the instruction starts at.
+	pub fn spanAt(&self, offset: usize) -> Option<Span> {
+		match self.spans.binary_search_by_key(&offset, |(off, _)| *off) {
+			Ok(idx) => Some(self.spans[idx].1),
+			Err(_) => self.debugInfo.as_ref()?.span(offset),
 		}
 	}
 
@@ -86,8 +709,12 @@ impl VM {
 		self.stack.push(value);
 	}
 
-	fn pop(&mut self) -> ValueType {
-		self.stack.pop().expect("stack is empty")
+	fn pop(&mut self) -> Result<ValueType, RuntimeError> {
+		self.stack.pop().ok_or_else(|| self.error(RuntimeErrorKind::StackUnderflow))
+	}
+
+	fn peek(&self) -> Result<&ValueType, RuntimeError> {
+		self.stack.last().ok_or_else(|| self.error(RuntimeErrorKind::StackUnderflow))
 	}
 }
 
@@ -97,23 +724,98 @@ impl From<Instructions> for VM {
 	}
 }
 
+impl From<Arc<[u8]>> for VM {
+	fn from(code: Arc<[u8]>) -> Self {
+		Self::new(code)
+	}
+}
+
+impl From<Container> for VM {
+	fn from(container: Container) -> Self {
+		let vm = Self::new(container.code())
+			.withConstants(container.constants().to_vec())
+			.withStrings(container.strings().to_vec())
+			.withFunctions(container.functions().to_vec());
+
+		match container.debugInfo() {
+			Some(debugInfo) => vm.withDebugInfo(debugInfo.clone()),
+			None => vm,
+		}
+	}
+}
+
+impl From<Assembled> for VM {
+	fn from(assembled: Assembled) -> Self {
+		Self::from(Container::from(assembled))
+	}
+}
+
 impl From<Vec<Instruction>> for VM {
 	fn from(instrs: Vec<Instruction>) -> Self {
-		Self::new(instrs.iter().flat_map(|instr| instr.bytes()).collect())
+		Self::new(instrs.iter().flat_map(|instr| instr.bytes()).collect::<Instructions>())
 	}
 }
 
-impl From<AST> for VM {
-	fn from(ast: AST) -> Self {
-		Self::from(ast.toInstructions())
+impl TryFrom<AST> for VM {
+	type Error = Diagnostics;
+
+	fn try_from(ast: AST) -> Result<Self, Self::Error> {
+		let mut code = Instructions::new();
+		let mut spans = Vec::new();
+
+		let compiled = ast.compile()?;
+
+		for (instr, span) in compiled.code {
+			spans.push((code.len(), span));
+			code.extend(instr.bytes());
+		}
+
+		Ok(Self {
+			spans,
+			..Self::new(code)
+				.withConstants(compiled.constants)
+				.withStrings(compiled.strings)
+				.withFunctions(compiled.functions)
+		})
 	}
 }
 
-impl Default for VM {
-	fn default() -> Self {
-		Self {
-			code:  Instructions::new(),
-			stack: Stack::new(),
+/// The next line without its line ending, or `None` at the end of the input.
+fn readLine(input: &mut dyn BufRead) -> io::Result<Option<String>> {
+	let mut line = String::new();
+	if input.read_line(&mut line)? == 0 {
+		return Ok(None);
+	}
+
+	if line.ends_with('\n') {
+		line.pop();
+		if line.ends_with('\r') {
+			line.pop();
 		}
 	}
+
+	Ok(Some(line))
+}
+
+/// The next UTF-8 encoded character, or `None` at the end of the input.
+fn readChar(input: &mut dyn BufRead) -> io::Result<Option<char>> {
+	let Some(&first) = input.fill_buf()?.first() else {
+		return Ok(None);
+	};
+
+	let len = match first {
+		0x00..=0x7f => 1,
+		0xc0..=0xdf => 2,
+		0xe0..=0xef => 3,
+		0xf0..=0xf7 => 4,
+		_ => return Err!("stream did not contain valid UTF-8", io::ErrorKind::InvalidData),
+	};
+
+	let mut bytes = [0; 4];
+	input.read_exact(&mut bytes[..len])?;
+
+	match std::str::from_utf8(&bytes[..len]) {
+		Ok(s) => Ok(s.chars().next()),
+		Err(_) => Err!("stream did not contain valid UTF-8", io::ErrorKind::InvalidData),
+	}
 }
@@ -0,0 +1,124 @@
+use nyooom_vm::{
+	assembler::assemble,
+	container::Container,
+	disassembler::Disassembler,
+	instruction::Instruction,
+	parser::Parser,
+	vm::VM,
+};
+
+type R = Result<(), Box<dyn std::error::Error>>;
+
+#[test]
+fn test_assemble() -> R {
+	let src = r#"
+; count to 3, the long way round
+    PUSH 0
+    DEFINE_GLOBAL "i"
+loop:
+    LOAD_GLOBAL "i"
+    PUSH 3
+    LESS
+    JUMP_IF_FALSE end       ; done counting
+    LOAD_GLOBAL "i"
+    PUSH 1
+    ADD
+    STORE_GLOBAL "i"
+    POP
+    JUMP loop
+end:
+    PUSH "a;b\n"
+    DEFINE_GLOBAL "s"
+    PUSH 'x'
+    PUSH 2.5f32
+    PUSH -7i32
+    PUSH 1e100
+    POP
+    POP
+    POP
+    DEFINE_GLOBAL "c"
+    HALT
+"#;
+
+	let mut vm = VM::from(assemble(src)?);
+	assert_eq!(vm.verify(), Ok(()));
+	vm.run()?;
+	assert_eq!(vm.global("i").map(|v| v.to_string()).as_deref(), Some("3"));
+	assert_eq!(vm.global("s").map(|v| v.to_string()).as_deref(), Some("a;b\n"));
+	assert_eq!(vm.global("c").map(|v| v.to_string()).as_deref(), Some("x"));
+
+	// labels resolve to relative jumps
+	let assembled = assemble("JUMP end\nPOP\nend:\nHALT")?;
+	assert!(matches!(assembled.code[..], [
+		Instruction::Jump(1),
+		Instruction::Pop,
+		Instruction::Halt
+	]));
+
+	// listed names keep their index, the others are added after them
+	let assembled = assemble(".string \"b\"\n.string \"a\"\nPUSH 1\nDEFINE_GLOBAL \"a\"\nPUSH 2\nDEFINE_GLOBAL 0\nPUSH 3\nDEFINE_GLOBAL \"c\"\nHALT")?;
+	assert_eq!(assembled.strings, ["b", "a", "c"]);
+	assert!(matches!(assembled.code[1], Instruction::DefineGlobal(1)));
+
+	let mut vm = VM::from(Container::from(assembled));
+	vm.run()?;
+	assert_eq!(vm.global("b").map(|v| v.to_string()).as_deref(), Some("2"));
+	assert_eq!(vm.global("c").map(|v| v.to_string()).as_deref(), Some("3"));
+
+	Ok(())
+}
+
+#[test]
+fn test_round_trip() -> R {
+	let src = "def counter() {\n\tlet n = 0;\n\tdef next() { n = n + 1; return n; }\n\treturn next;\n}\nlet next = counter();\nnext();\nlet r = next() ** 2;\nprint \"tab\\t\";";
+	let container = Container::try_from(Parser::parse(src)?)?;
+	let text = Disassembler::new(container.code())
+		.withConstants(container.constants())
+		.withStrings(container.strings())
+		.withFunctions(container.functions())
+		.disassemble()?;
+
+	assert!(text.starts_with(
+		".const 0\n.const 1\n.const 2\n.const \"tab\\t\"\n.string \"counter\"\n.string \"next\"\n.string \"r\"\n.function next/0 L1\n.function counter/0 L0\n"
+	));
+	assert!(text.contains("\n0023  PUSH_FUNCTION 0               ; fn next/0 L1\n"));
+
+	let mut vm = VM::from(assemble(&text)?);
+	assert_eq!(vm.disassemble()?, text);
+	vm.run()?;
+	assert_eq!(vm.global("r").map(|v| v.to_string()).as_deref(), Some("4"));
+
+	// the output of the disassembler with source lines assembles to the same code
+	let vm = VM::try_from(Parser::parse(src)?)?;
+	assert_eq!(VM::from(assemble(&vm.disassemble()?)?).disassemble()?, text);
+
+	let literals = ".function f/0 L0\n0000  PUSH_FUNCTION 0               ; fn f/0 L0\n0005  PUSH -0.0f32\n000b  PUSH 'a'\n0011  PUSH \"\\u{7f}\\\"\"\n0017  LOAD_GLOBAL 3                 ; no such string\n001c  JUMP_IF_TRUE_OR_POP @9\nL0:\n0021  CLOSURE local 1, upvalue 0\n002a  HALT\n";
+	assert_eq!(VM::from(assemble(literals)?).disassemble()?, literals);
+
+	Ok(())
+}
+
+#[test]
+fn test_errors() {
+	let error = |src: &str| assemble(src).unwrap_err();
+
+	let e = error("PUSH 1\nFLY");
+	assert_eq!(e.line(), 2);
+	assert_eq!(e.to_string(), "unknown instruction 'FLY' on line 2");
+
+	assert_eq!(error("JUMP nowhere").message(), "undefined label 'nowhere'");
+	assert_eq!(error("a:\na:\nHALT").message(), "label 'a' is defined more than once");
+	assert_eq!(error("LOAD_LOCAL x").message(), "'x' is not a valid number here");
+	assert_eq!(error("PUSH \"open").message(), "unterminated \"quoted\" operand");
+	assert_eq!(error("POP 1").message(), "unexpected '1' after the operands");
+	assert_eq!(
+		error("LOAD_GLOBAL x").message(),
+		"expected a \"quoted\" name or a string index, found 'x'"
+	);
+	assert_eq!(error(".const").message(), "expected a value");
+	assert_eq!(error(".const fn").message(), "'fn' is not a valid value");
+	assert_eq!(error(".string x").message(), "expected a \"quoted\" operand");
+	assert_eq!(error(".function f/0 nowhere\nHALT").message(), "undefined label 'nowhere'");
+	assert_eq!(error(".function f L0").message(), "expected name/arity, found 'f'");
+}
+
@@ -0,0 +1,99 @@
+use std::{
+	fs,
+	io::Write,
+	path::PathBuf,
+	process::{Command, Output, Stdio},
+};
+
+/// A fresh directory for the files of one test.
+fn dir(name: &str) -> PathBuf {
+	let dir = std::env::temp_dir().join(format!("nyooom-cli-{name}-{}", std::process::id()));
+	let _ = fs::remove_dir_all(&dir);
+	fs::create_dir_all(&dir).unwrap();
+	dir
+}
+
+fn nyooom(dir: &PathBuf, args: &[&str], input: &str) -> Output {
+	let mut child = Command::new(env!("CARGO_BIN_EXE_nyooom"))
+		.args(args)
+		.current_dir(dir)
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()
+		.unwrap();
+
+	child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+	child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+	String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+	String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn test_run_and_compile() {
+	let dir = dir("run");
+	fs::write(dir.join("echo.ny"), "print readline();\nlet code = 3;\ncode = code * 2;").unwrap();
+
+	let output = nyooom(&dir, &["run", "echo.ny"], "hello\n");
+	assert_eq!(output.status.code(), Some(0));
+	assert_eq!(stdout(&output), "hello");
+
+	let output = nyooom(&dir, &["compile", "echo.ny"], "");
+	assert_eq!(output.status.code(), Some(0));
+	assert!(fs::read(dir.join("echo.nyb")).unwrap().starts_with(b"nyb"));
+
+	let output = nyooom(&dir, &["run", "echo.nyb"], "again\n");
+	assert_eq!(output.status.code(), Some(0));
+	assert_eq!(stdout(&output), "again");
+
+	let output = nyooom(&dir, &["disassemble", "echo.nyb"], "");
+	assert!(stdout(&output).contains("\n0000     1  READ_LINE\n0001     |  PRINT\n"));
+
+	let output = nyooom(&dir, &["compile", "echo.ny", "--strip", "-o", "stripped.nyb"], "");
+	assert_eq!(output.status.code(), Some(0));
+	let output = nyooom(&dir, &["disassemble", "stripped.nyb"], "");
+	assert!(stdout(&output).contains("\n0000  READ_LINE\n0001  PRINT\n"));
+
+	fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_errors() {
+	let dir = dir("errors");
+	fs::write(dir.join("syntax.ny"), "let x = ;").unwrap();
+	fs::write(dir.join("fail.ny"), "def f(v) {\n  return v / 0;\n}\nf(1);").unwrap();
+
+	let output = nyooom(&dir, &["check", "syntax.ny"], "");
+	assert_eq!(output.status.code(), Some(1));
+	assert!(stderr(&output).starts_with("error[E0201]: expected expression, found ';'\n --> syntax.ny:1:9\n"));
+
+	let output = nyooom(&dir, &["check", "fail.ny"], "");
+	assert_eq!(output.status.code(), Some(0));
+
+	let output = nyooom(&dir, &["run", "fail.ny"], "");
+	assert_eq!(output.status.code(), Some(1));
+	assert_eq!(
+		stderr(&output),
+		"Traceback (most recent call last):
+  File \"fail.ny\", line 4, in <script>
+  File \"fail.ny\", line 2, in f
+error[E0412]: cannot divide by 0
+"
+	);
+
+	let output = nyooom(&dir, &["run", "missing.ny"], "");
+	assert_eq!(output.status.code(), Some(1));
+	assert!(stderr(&output).starts_with("error: cannot read missing.ny: "));
+
+	let output = nyooom(&dir, &["frobnicate"], "");
+	assert_eq!(output.status.code(), Some(2));
+	assert!(stderr(&output).starts_with("error: unknown command 'frobnicate'\n\nusage: nyooom"));
+
+	fs::remove_dir_all(dir).unwrap();
+}
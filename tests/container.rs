@@ -0,0 +1,202 @@
+use nyooom_vm::{
+	bytes::ToBytes,
+	container::{Container, ContainerError, FLAG_DEBUG_INFO, FORMAT_VERSION},
+	debuginfo::DebugInfo,
+	parser::Parser,
+	vm::VM,
+	MAGIC_NUMBER,
+};
+
+type R = Result<(), Box<dyn std::error::Error>>;
+
+fn compiled(src: &str) -> Result<Container, Box<dyn std::error::Error>> {
+	Ok(Container::try_from(Parser::parse(src)?)?)
+}
+
+#[test]
+fn test_round_trip() -> R {
+	let container = compiled("def add(a, b) { return a + b; }\nlet r = add(1, 2);")?;
+	assert_eq!(container.strings(), ["add", "r"]);
+	assert_eq!(container.functions().len(), 1);
+	assert_eq!(container.functions()[0].name(), "add");
+
+	let info = container.debugInfo().cloned().unwrap();
+	assert_eq!(info.location(0), Some((1, 1)));
+
+	let bytes = container.bytes();
+	assert!(bytes.starts_with(&MAGIC_NUMBER));
+	assert_eq!(bytes[3..5], FORMAT_VERSION.to_le_bytes());
+	assert_eq!(bytes[5..7], FLAG_DEBUG_INFO.to_le_bytes());
+
+	let mut loaded = Container::read(&bytes)?;
+	assert_eq!(loaded.code(), container.code());
+	assert_eq!(loaded.strings(), ["add", "r"]);
+	assert_eq!(loaded.functions()[0].name(), "add");
+	assert_eq!(loaded.functions()[0].arity(), 2);
+	assert_eq!(loaded.debugInfo(), Some(&info));
+
+	loaded.stripDebugInfo();
+	assert_eq!(loaded.flags(), 0);
+	assert_eq!(Container::read(&loaded.bytes())?.debugInfo(), None);
+
+	let mut vm = VM::from(loaded);
+	vm.run()?;
+	assert_eq!(vm.global("r").map(ToString::to_string).as_deref(), Some("3"));
+
+	Ok(())
+}
+
+#[test]
+fn test_unknown_sections_are_skipped() -> R {
+	let mut bytes = MAGIC_NUMBER.to_vec();
+	bytes.extend(FORMAT_VERSION.to_le_bytes());
+	bytes.extend([0x00, 0x00, 0x02]);
+	bytes.extend([0x7f, 0x02, 0x00, 0x00, 0x00, 0xaa, 0xbb]);
+	bytes.extend([0x01, 0x01, 0x00, 0x00, 0x00, 0x00]);
+
+	assert_eq!(Container::read(&bytes)?.code(), [0x00]);
+
+	Ok(())
+}
+
+#[test]
+fn test_errors() {
+	assert_eq!(Container::read(b"#!/bin/sh").unwrap_err(), ContainerError::NotBytecode);
+
+	let mut bytes = Container::new(vec![0x00]).bytes();
+	bytes[3] = 0x2a;
+	let error = Container::read(&bytes).unwrap_err();
+	assert_eq!(error, ContainerError::UnsupportedVersion(42));
+	assert_eq!(
+		error.to_string(),
+		format!("bytecode format version 42 is not supported, expected version {FORMAT_VERSION}")
+	);
+
+	let mut bytes = MAGIC_NUMBER.to_vec();
+	bytes.extend(FORMAT_VERSION.to_le_bytes());
+	bytes.extend([0x00, 0x00, 0x00]);
+	assert_eq!(Container::read(&bytes).unwrap_err(), ContainerError::MissingSection("code"));
+
+	let mut bytes = Container::new(vec![0x00]).bytes();
+	bytes[5] = 0x03;
+	let error = Container::read(&bytes).unwrap_err();
+	assert_eq!(error, ContainerError::UnsupportedFlags(0x02));
+	assert_eq!(error.to_string(), "bytecode flags 0x0002 are not supported");
+
+	// the flag promises debug info the file doesn't have
+	bytes[5] = 0x01;
+	assert_eq!(
+		Container::read(&bytes).unwrap_err(),
+		ContainerError::MissingSection("debug info")
+	);
+
+	// the code section claims more bytes than the file has
+	let mut bytes = Container::new(vec![0x00]).bytes();
+	bytes.pop();
+	assert!(matches!(Container::read(&bytes).unwrap_err(), ContainerError::Decode(_)));
+
+	// a string table whose only entry runs past the end of its section
+	let mut bytes = Container::new(vec![0x00]).withStrings(vec!["abc".into()]).bytes();
+	let len = bytes.len();
+	bytes[len - 5] = 0x04;
+	assert!(matches!(Container::read(&bytes).unwrap_err(), ContainerError::Decode(_)));
+
+	// a constant pool whose only string runs past the end of its section
+	let mut container = compiled("\"abc\";").unwrap();
+	container.stripDebugInfo();
+	let mut bytes = container.bytes();
+	let len = bytes.len();
+	bytes[len - 5] = 0x04;
+	assert!(matches!(Container::read(&bytes).unwrap_err(), ContainerError::Decode(_)));
+}
+
+#[test]
+fn test_constant_pool() -> R {
+	let container = compiled("let a = \"hello\";\nlet b = 2.5;\nlet c = \"hello\" + \"hello\";\nlet d = b * 2.5;")?;
+	let constants: Vec<_> = container.constants().iter().map(ToString::to_string).collect();
+	assert_eq!(constants, ["hello", "2.5"]);
+
+	let loaded = Container::read(&container.bytes())?;
+	assert_eq!(loaded.constants().len(), 2);
+
+	let mut vm = VM::from(loaded);
+	assert_eq!(vm.verify(), Ok(()));
+	vm.run()?;
+	assert_eq!(vm.global("c").map(ToString::to_string).as_deref(), Some("hellohello"));
+	assert_eq!(vm.global("d").map(ToString::to_string).as_deref(), Some("6.25"));
+
+	Ok(())
+}
+
+#[test]
+fn test_non_ascii() -> R {
+	let container = compiled("let s = \"héllo ✓\";\nlet c = 'é';")?;
+
+	let mut vm = VM::from(Container::read(&container.bytes())?);
+	vm.run()?;
+	assert_eq!(vm.global("s").map(ToString::to_string).as_deref(), Some("héllo ✓"));
+	assert_eq!(vm.global("c").map(ToString::to_string).as_deref(), Some("é"));
+
+	Ok(())
+}
+
+#[test]
+#[should_panic(expected = "debug info offset 3 pushed after offset 5")]
+fn test_debug_info_out_of_order() {
+	let mut info = DebugInfo::new();
+	info.push(5, 1, 1);
+	info.push(3, 2, 1);
+}
+
+#[test]
+fn test_debug_info() -> R {
+	let mut info = DebugInfo::new();
+	info.push(0, 1, 1);
+	info.push(5, 1, 1);
+	info.push(9, 300, 7);
+	info.push(12, 2, 40);
+	assert_eq!(info.len(), 3);
+	assert_eq!(info.location(7), Some((1, 1)));
+	assert_eq!(info.location(9), Some((300, 7)));
+	assert_eq!(info.location(100), Some((2, 40)));
+
+	let bytes = Container::new(vec![0x00]).withDebugInfo(info.clone()).bytes();
+	assert_eq!(Container::read(&bytes)?.debugInfo(), Some(&info));
+
+	let info = info.withFile("main.nyo");
+	let bytes = Container::new(vec![0x00]).withDebugInfo(info.clone()).bytes();
+	assert_eq!(
+		Container::read(&bytes)?.debugInfo().and_then(DebugInfo::file),
+		Some("main.nyo")
+	);
+
+	// a line difference that overflows
+	let mut bytes = MAGIC_NUMBER.to_vec();
+	bytes.extend(FORMAT_VERSION.to_le_bytes());
+	bytes.extend(FLAG_DEBUG_INFO.to_le_bytes());
+	bytes.extend([0x02, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00]);
+	let table = [
+		&[0x00, 0x00, 0x02, 0x00, 0x00, 0x00][..],
+		&[0x00, 0x02, 0x01],
+		&[0x00, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01, 0x01],
+	]
+	.concat();
+	bytes.push(0x05);
+	bytes.extend((table.len() as u32).to_le_bytes());
+	bytes.extend(&table);
+	let error = Container::read(&bytes).unwrap_err();
+	assert_eq!(error.to_string(), "invalid debug info at offset 28");
+
+	// runtime errors of a loaded program point at the source, unless the debug info was stripped
+	let container = compiled("let a = 1;\nlet b = a + \"x\" * 2;")?;
+	let error = VM::from(Container::read(&container.bytes())?).run().unwrap_err();
+	assert_eq!(error.span().map(|span| span.to_string()).as_deref(), Some("2:13"));
+
+	let mut stripped = container;
+	stripped.stripDebugInfo();
+	let error = VM::from(Container::read(&stripped.bytes())?).run().unwrap_err();
+	assert!(error.span().is_none());
+	assert!(error.diagnostic().notes()[0].starts_with("at bytecode offset"));
+
+	Ok(())
+}
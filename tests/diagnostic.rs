@@ -0,0 +1,95 @@
+use nyooom_vm::{
+	diagnostic::{Diagnostic, ERR_INVALID_CHARACTER, ERR_UNEXPECTED_TOKEN, ERR_UNTERMINATED_STRING},
+	parser::Parser,
+	span::{Position, Span},
+	tokenizer::Tokenizer,
+};
+
+#[test]
+fn test_tokenizer_reports_every_error() {
+	let errors = Tokenizer::tokenize("let a = $;\nlet b = \"oops".as_bytes().iter().peekable()).unwrap_err();
+
+	let codes: Vec<_> = errors.iter().map(Diagnostic::code).collect();
+	assert_eq!(codes, vec![ERR_INVALID_CHARACTER, ERR_UNTERMINATED_STRING]);
+
+	let lines: Vec<_> = errors.iter().map(|e| e.primary().unwrap().span().line()).collect();
+	assert_eq!(lines, vec![1, 2]);
+}
+
+#[test]
+fn test_parser_error() {
+	let errors = Parser::parse("print 1 +;").unwrap_err();
+
+	assert_eq!(errors.len(), 1);
+	assert_eq!(errors[0].code(), ERR_UNEXPECTED_TOKEN);
+	assert_eq!(errors[0].message(), "expected expression, found ';'");
+	assert_eq!(errors[0].primary().unwrap().span().range(), 9..10);
+}
+
+#[test]
+fn test_render() {
+	let source = "let x = 1;\n\tprint x +;\n";
+	let errors = Parser::parse(source).unwrap_err();
+
+	assert_eq!(
+		errors.render(source, "main.ny"),
+		"error[E0201]: expected expression, found ';'
+ --> main.ny:2:11
+  |
+2 | \tprint x +;
+  | \t         ^ expected expression
+"
+	);
+}
+
+#[test]
+fn test_render_non_ascii() {
+	let source = "let s = \"é\"; é;";
+	let errors = Tokenizer::tokenize(source.as_bytes().iter().peekable()).unwrap_err();
+
+	assert_eq!(errors.len(), 1);
+	assert_eq!(
+		errors.render(source, "main.ny"),
+		"error[E0101]: invalid character 'é'
+ --> main.ny:1:15
+  |
+1 | let s = \"é\"; é;
+  |              ^ invalid character 'é'
+"
+	);
+
+	// a span that ends in the middle of a character still gets an underline
+	let at = |column, offset| Position::new(1, column, offset);
+	let diagnostic = Diagnostic::error("E9999", "cut off").withPrimary(Span::new(at(11, 10), at(12, 11)), "here");
+	assert!(diagnostic.render(source, "<input>").ends_with("\n  |           ^ here\n"));
+}
+
+#[test]
+fn test_render_labels() {
+	let source = "a + b;\nc;";
+	let at = |line, column, offset| Position::new(line, column, offset);
+
+	let diagnostic = Diagnostic::error("E9999", "something went wrong")
+		.withPrimary(Span::new(at(1, 5, 4), at(1, 6, 5)), "this")
+		.withSecondary(Span::new(at(1, 1, 0), at(1, 2, 1)), "and that")
+		.withSecondary(Span::new(at(2, 1, 7), at(2, 2, 8)), "")
+		.withNote("a note")
+		.withHelp("try something else");
+
+	assert_eq!(
+		diagnostic.render(source, "<input>"),
+		"error[E9999]: something went wrong
+ --> <input>:1:5
+  |
+1 | a + b;
+  | - and that
+  |     ^ this
+2 | c;
+  | -
+  |
+  = note: a note
+  = help: try something else
+"
+	);
+	assert_eq!(diagnostic.to_string(), "error[E9999]: something went wrong (1:5)");
+}
@@ -0,0 +1,64 @@
+use nyooom_vm::{parser::Parser, vm::VM};
+
+type R = Result<(), Box<dyn std::error::Error>>;
+
+#[test]
+fn test_disassemble() -> R {
+	let src = "def twice(x) { return x * 2; }\nlet i = 0;\nwhile (i < 3) {\n\ti = twice(i);\n}\nprint \"done\";";
+	let vm = VM::try_from(Parser::parse(src)?)?;
+
+	let expected = r#".const 2
+.const 0
+.const 3
+.const "done"
+.string "twice"
+.string "i"
+.function twice/1 L0
+0000     1  JUMP L1
+L0:
+0005     |  LOAD_LOCAL 1
+0008     |  PUSH_CONST 0                  ; 2
+000d     |  MUL
+000e     |  RETURN
+000f     |  PUSH null
+0011     |  RETURN
+L1:
+0012     |  PUSH_FUNCTION 0               ; fn twice/1 L0
+0017     |  DEFINE_GLOBAL "twice"
+001c     2  PUSH_CONST 1                  ; 0
+0021     |  DEFINE_GLOBAL "i"
+L2:
+0026     3  LOAD_GLOBAL "i"
+002b     |  PUSH_CONST 2                  ; 3
+0030     |  LESS
+0031     |  JUMP_IF_FALSE L3
+0036     4  LOAD_GLOBAL "twice"
+003b     |  LOAD_GLOBAL "i"
+0040     |  CALL 1
+0042     |  STORE_GLOBAL "i"
+0047     |  POP
+0048     3  JUMP L2
+L3:
+004d     6  PUSH_CONST 3                  ; "done"
+0052     |  PRINT
+0053     1  HALT
+"#;
+	assert_eq!(vm.disassemble()?, expected);
+
+	Ok(())
+}
+
+#[test]
+fn test_disassemble_bytes() -> R {
+	// PUSH 7i32, JUMP +1, PUSH_CONST 9, HALT
+	let code = vec![
+		0x01, 0x13, 0x07, 0x00, 0x00, 0x00, 0x50, 0x01, 0x00, 0x00, 0x00, 0x03, 0x09, 0x00, 0x00, 0x00, 0x00,
+	];
+	let expected = "0000  PUSH 7i32\n0006  JUMP @12\n000b  PUSH_CONST 9                  ; no such constant\n0010  HALT\n";
+	assert_eq!(VM::new(code).disassemble()?, expected);
+
+	let error = VM::new(vec![0x01, 0xff]).disassemble().unwrap_err();
+	assert_eq!(error.to_string(), "unknown value 0xff at offset 1");
+
+	Ok(())
+}
@@ -0,0 +1,185 @@
+use std::io;
+
+use nyooom_vm::{
+	ast::node::{Node, NodeKind},
+	parser::Parser,
+	token::{Token, TokenValue},
+};
+
+type R = io::Result<()>;
+
+fn name(token: &Token) -> String {
+	match token.value() {
+		TokenValue::Identifier(name) => name,
+		_ => token.kind().to_string(),
+	}
+}
+
+fn opt(node: &Option<Box<Node>>) -> String {
+	node.as_ref().map_or(String::from("_"), |n| sexpr(n))
+}
+
+fn list(nodes: &[Node]) -> String {
+	nodes.iter().map(sexpr).collect::<Vec<_>>().join(" ")
+}
+
+/// Render a node as an s-expression so trees can be compared as strings.
+fn sexpr(node: &Node) -> String {
+	match node.kind() {
+		NodeKind::BinaryExpression(op, a, b) => format!("({} {} {})", name(op).trim_matches('\''), sexpr(a), sexpr(b)),
+		NodeKind::Block(body) => format!("{{{}}}", list(body)),
+		NodeKind::CallExpression(callee, args) => format!("(call {} [{}])", sexpr(callee), list(args)),
+		NodeKind::Class(n, sup, body) => format!("(class {} {} {})", sexpr(n), opt(sup), sexpr(body)),
+		NodeKind::DoWhileStatement(body, test) => format!("(do {} {})", sexpr(body), sexpr(test)),
+		NodeKind::EmptyStatement => String::from(";"),
+		NodeKind::Error => String::from("<error>"),
+		NodeKind::ExpressionStatement(expr) => format!("{};", sexpr(expr)),
+		NodeKind::ForStatement(init, test, update, body) => {
+			format!("(for {} {} {} {})", opt(init), opt(test), opt(update), sexpr(body))
+		}
+		NodeKind::Function(n, params, body) => format!("(def {} [{}] {})", sexpr(n), list(params), sexpr(body)),
+		NodeKind::Identifier(tk) => name(tk),
+		NodeKind::IfStatement(test, cons, alt) => format!("(if {} {} {})", sexpr(test), sexpr(cons), opt(alt)),
+		NodeKind::Literal(lit) => lit.value().to_string(),
+		NodeKind::MemberExpression(computed, obj, prop) => {
+			if *computed {
+				format!("{}[{}]", sexpr(obj), sexpr(prop))
+			} else {
+				format!("{}.{}", sexpr(obj), sexpr(prop))
+			}
+		}
+		NodeKind::PrintExpression(v) => format!("(print {})", sexpr(v)),
+		NodeKind::Program(body) => list(body),
+		NodeKind::ReadCharExpression => String::from("(readchar)"),
+		NodeKind::ReadLineExpression => String::from("(readline)"),
+		NodeKind::ReturnStatement(v) => format!("(return {})", opt(v)),
+		NodeKind::SuperExpression => String::from("super"),
+		NodeKind::ThisExpression => String::from("this"),
+		NodeKind::UnaryExpression(op, v) => format!("({} {})", name(op).trim_matches('\''), sexpr(v)),
+		NodeKind::VariableDeclaration(n, init) => format!("{} = {}", sexpr(n), sexpr(init)),
+		NodeKind::VariableStatement(decls) => format!("(let {})", decls.iter().map(sexpr).collect::<Vec<_>>().join(", ")),
+		NodeKind::WhileStatement(test, body) => format!("(while {} {})", sexpr(test), sexpr(body)),
+	}
+}
+
+fn p(s: &str) -> io::Result<String> {
+	Ok(sexpr(Parser::parse(s)?.program()))
+}
+
+#[test]
+fn test_empty() -> R {
+	assert_eq!(p("")?, "");
+	assert_eq!(p(";")?, ";");
+
+	Ok(())
+}
+
+#[test]
+fn test_precedence() -> R {
+	assert_eq!(p("1 + 2 * 3;")?, "(+ 1 (* 2 3));");
+	assert_eq!(p("(1 + 2) * 3;")?, "(* (+ 1 2) 3);");
+	assert_eq!(p("1 - 2 - 3;")?, "(- (- 1 2) 3);");
+	assert_eq!(p("a || b && c;")?, "(|| a (&& b c));");
+	assert_eq!(p("a < b | c & d + e;")?, "(< a (| b (& c (+ d e))));");
+	assert_eq!(p("a == b <= c && d != e >= f;")?, "(&& (== a (<= b c)) (!= d (>= e f)));");
+	assert_eq!(p("-a * !b;")?, "(* (- a) (! b));");
+	assert_eq!(p("~-a + !!b;")?, "(+ (~ (- a)) (! (! b)));");
+	assert_eq!(p("-a ** b ** -c * d;")?, "(* (- (** a (** b (- c)))) d);");
+	assert_eq!(p("a ^ b | c << d + e & f >> g;")?, "(| (^ a b) (& (<< c (+ d e)) (>> f g)));");
+	assert_eq!(p("a = b = 1 + 2;")?, "(= a (= b (+ 1 2)));");
+
+	Ok(())
+}
+
+#[test]
+fn test_postfix() -> R {
+	assert_eq!(p("a.b(1, 2)[c].d = 3;")?, "(= (call a.b [1 2])[c].d 3);");
+	assert_eq!(p("f()();")?, "(call (call f []) []);");
+	assert_eq!(p("super.init(this);")?, "(call super.init [this]);");
+
+	Ok(())
+}
+
+#[test]
+fn test_statements() -> R {
+	assert_eq!(p("let a = 1, b;")?, "(let a = 1, b = null)");
+	assert_eq!(p("print \"hi\";")?, "(print hi)");
+	assert_eq!(p("if (a) b; else { c; }")?, "(if a b; {c;})");
+	assert_eq!(p("while (a) {}")?, "(while a {})");
+	assert_eq!(p("do a; while (b);")?, "(do a; b)");
+	assert_eq!(
+		p("for (let i = 0; i < 3; i = i + 1) print i;")?,
+		"(for (let i = 0) (< i 3) (= i (+ i 1)) (print i))"
+	);
+	assert_eq!(p("for (;;) {}")?, "(for _ _ _ {})");
+	assert_eq!(
+		p("let l = readline(), c = readchar();")?,
+		"(let l = (readline), c = (readchar))"
+	);
+
+	Ok(())
+}
+
+#[test]
+fn test_declarations() -> R {
+	assert_eq!(p("def add(a, b) { return a + b; }")?, "(def add [a b] {(return (+ a b))})");
+	assert_eq!(
+		p("class B : A { def init() { super.init(); return; } }")?,
+		"(class B A {(def init [] {(call super.init []); (return _)})})"
+	);
+
+	Ok(())
+}
+
+#[test]
+fn test_errors() {
+	for src in [
+		"1 +;",
+		"let = 1;",
+		"1 = 2;",
+		"print 1",
+		"if a {}",
+		"class A { let x; }",
+		"super;",
+		"readline;",
+		"readchar(1);",
+	] {
+		assert!(Parser::parse(src).is_err(), "{src:?} should not parse");
+	}
+}
+
+#[test]
+fn test_spans() -> R {
+	let ast = Parser::parse("let a = 1;\nprint a + 22;")?;
+	let NodeKind::Program(body) = ast.program().kind() else {
+		panic!("expected program")
+	};
+
+	assert_eq!(body[0].span().range(), 0..10);
+	assert_eq!(body[1].span().range(), 11..24);
+	assert_eq!((body[1].span().line(), body[1].span().column()), (2, 1));
+
+	let NodeKind::PrintExpression(sum) = body[1].kind() else {
+		panic!("expected print")
+	};
+	assert_eq!(sum.span().range(), 17..23);
+	assert_eq!((sum.span().column(), sum.span().endColumn()), (7, 13));
+
+	Ok(())
+}
+
+#[test]
+fn test_recovery() {
+	let src = "let a = ;\nprint a;\ndef f() { 1 +; return 2; }\nclass A { let x; def m() {} }\n}\nprint $;";
+	let (ast, errors) = Parser::parseRecovering(src);
+
+	assert_eq!(
+		sexpr(ast.program()),
+		"<error> (print a) (def f [] {<error> (return 2)}) (class A _ {<error> (def m [] {})}) <error> (print <error>)"
+	);
+
+	let lines: Vec<_> = errors.iter().map(|e| e.primary().unwrap().span().line()).collect();
+	assert_eq!(lines, vec![6, 1, 3, 4, 5]);
+
+	assert_eq!(Parser::parse(src).unwrap_err(), errors);
+}
@@ -1,19 +1,28 @@
 use std::io;
 
 use nyooom_vm::{
-	token::{tokenkind::TokenKind, Token, TokenValue},
+	span::{Position, Span},
+	token::{symbol::Symbol, tokenkind::TokenKind, Token, TokenValue},
 	tokenizer::Tokenizer,
 };
 
 type R = io::Result<()>;
 
-fn t<'a>(s: &'a str) -> io::Result<Vec<Token>> {
-	Tokenizer::tokenize(s.as_bytes().iter().peekable())
+/// Span of bytes `start..end` on the first line.
+fn span(start: usize, end: usize) -> Span {
+	Span::new(
+		Position::new(1, start as u32 + 1, start),
+		Position::new(1, end as u32 + 1, end),
+	)
+}
+
+fn t(s: &str) -> io::Result<Vec<Token>> {
+	Ok(Tokenizer::tokenize(s.as_bytes().iter().peekable())?)
 }
 
 #[test]
 fn test_empty() -> R {
-	assert_eq!(t("")?, vec![Token::new(TokenKind::EOF, TokenValue::None, 1)]);
+	assert_eq!(t("")?, vec![Token::new(TokenKind::EOF, TokenValue::None, span(0, 0))]);
 
 	Ok(())
 }
@@ -21,8 +30,8 @@ fn test_empty() -> R {
 #[test]
 fn test_null() -> R {
 	assert_eq!(t("null")?, vec![
-		Token::new(TokenKind::Null, TokenValue::Null, 1),
-		Token::new(TokenKind::EOF, TokenValue::None, 1)
+		Token::new(TokenKind::Null, TokenValue::Null, span(0, 4)),
+		Token::new(TokenKind::EOF, TokenValue::None, span(4, 4))
 	]);
 
 	Ok(())
@@ -31,12 +40,12 @@ fn test_null() -> R {
 #[test]
 fn test_bool() -> R {
 	assert_eq!(t("true")?, vec![
-		Token::new(TokenKind::Bool, TokenValue::True, 1),
-		Token::new(TokenKind::EOF, TokenValue::None, 1)
+		Token::new(TokenKind::Bool, TokenValue::True, span(0, 4)),
+		Token::new(TokenKind::EOF, TokenValue::None, span(4, 4))
 	]);
 	assert_eq!(t("false")?, vec![
-		Token::new(TokenKind::Bool, TokenValue::False, 1),
-		Token::new(TokenKind::EOF, TokenValue::None, 1)
+		Token::new(TokenKind::Bool, TokenValue::False, span(0, 5)),
+		Token::new(TokenKind::EOF, TokenValue::None, span(5, 5))
 	]);
 
 	Ok(())
@@ -45,18 +54,19 @@ fn test_bool() -> R {
 #[test]
 fn test_int() -> R {
 	assert_eq!(t("42")?, vec![
-		Token::new(TokenKind::Int, TokenValue::Int(42), 1),
-		Token::new(TokenKind::EOF, TokenValue::None, 1)
+		Token::new(TokenKind::Int, TokenValue::Int(42), span(0, 2)),
+		Token::new(TokenKind::EOF, TokenValue::None, span(2, 2))
 	]);
 
 	Ok(())
 }
 
 #[test]
+#[allow(clippy::approx_constant)]
 fn test_float() -> R {
 	assert_eq!(t("3.14159")?, vec![
-		Token::new(TokenKind::Float, TokenValue::Float(3.14159), 1),
-		Token::new(TokenKind::EOF, TokenValue::None, 1)
+		Token::new(TokenKind::Float, TokenValue::Float(3.14159), span(0, 7)),
+		Token::new(TokenKind::EOF, TokenValue::None, span(7, 7))
 	]);
 
 	Ok(())
@@ -65,23 +75,104 @@ fn test_float() -> R {
 #[test]
 fn test_char() -> R {
 	for c in ['a', '\n', '\0'] {
+		let end = if c == '\n' {
+			Position::new(2, 2, 3)
+		} else {
+			Position::new(1, 4, 3)
+		};
 		assert_eq!(t(format!("'{}'", &c).as_str())?, vec![
-			Token::new(TokenKind::Char, TokenValue::Char(c), 1),
-			Token::new(TokenKind::EOF, TokenValue::None, 1)
+			Token::new(TokenKind::Char, TokenValue::Char(c), Span::new(Position::default(), end)),
+			Token::new(TokenKind::EOF, TokenValue::None, Span::new(end, end))
 		])
 	}
 
+	// columns count bytes, so the two-byte 'é' ends a column later
+	assert_eq!(t("'é'")?, vec![
+		Token::new(TokenKind::Char, TokenValue::Char('é'), span(0, 4)),
+		Token::new(TokenKind::EOF, TokenValue::None, span(4, 4))
+	]);
+
 	Ok(())
 }
 
 #[test]
 fn test_string() -> R {
-	for s in ["", "Hello World!", "true"] {
-		assert_eq!(t(format!("{:?}", &s).as_str())?, vec![
-			Token::new(TokenKind::Str, TokenValue::Str(s.to_string()), 1),
-			Token::new(TokenKind::EOF, TokenValue::None, 1)
+	for s in ["", "Hello World!", "true", "héllo ✓"] {
+		let src = format!("{:?}", &s);
+		let len = src.len();
+		assert_eq!(t(src.as_str())?, vec![
+			Token::new(TokenKind::Str, TokenValue::Str(s.to_string()), span(0, len)),
+			Token::new(TokenKind::EOF, TokenValue::None, span(len, len))
 		])
 	}
 
 	Ok(())
 }
+
+#[test]
+fn test_compound() -> R {
+	let kinds: Vec<_> = t("a <= b == !c != d >= e && f || g < h ** i << j >> k")?
+		.iter()
+		.map(Token::kind)
+		.collect();
+
+	assert_eq!(kinds, vec![
+		TokenKind::Identifier,
+		TokenKind::Compound(Symbol::Lesser, Symbol::Equal),
+		TokenKind::Identifier,
+		TokenKind::Compound(Symbol::Equal, Symbol::Equal),
+		TokenKind::Symbol(Symbol::Bang),
+		TokenKind::Identifier,
+		TokenKind::Compound(Symbol::Bang, Symbol::Equal),
+		TokenKind::Identifier,
+		TokenKind::Compound(Symbol::Greater, Symbol::Equal),
+		TokenKind::Identifier,
+		TokenKind::Compound(Symbol::Ampersand, Symbol::Ampersand),
+		TokenKind::Identifier,
+		TokenKind::Compound(Symbol::Pipe, Symbol::Pipe),
+		TokenKind::Identifier,
+		TokenKind::Symbol(Symbol::Lesser),
+		TokenKind::Identifier,
+		TokenKind::Compound(Symbol::Asterisk, Symbol::Asterisk),
+		TokenKind::Identifier,
+		TokenKind::Compound(Symbol::Lesser, Symbol::Lesser),
+		TokenKind::Identifier,
+		TokenKind::Compound(Symbol::Greater, Symbol::Greater),
+		TokenKind::Identifier,
+		TokenKind::EOF,
+	]);
+
+	Ok(())
+}
+
+#[test]
+fn test_spans() -> R {
+	let tokens = t("let x = 1;\n  // comment\n\tprint x;")?;
+	let spans: Vec<_> = tokens
+		.iter()
+		.map(|tk| (tk.line(), tk.span().column(), tk.span().range()))
+		.collect();
+
+	assert_eq!(spans, vec![
+		(1, 1, 0..3),
+		(1, 5, 4..5),
+		(1, 7, 6..7),
+		(1, 9, 8..9),
+		(1, 10, 9..10),
+		(3, 2, 25..30),
+		(3, 8, 31..32),
+		(3, 9, 32..33),
+		(3, 10, 33..33),
+	]);
+
+	// lines and columns past what fits in 16 bits
+	let long = format!("{}x", " ".repeat(70_000));
+	let tokens = t(&long)?;
+	assert_eq!(tokens[0].span().column(), 70_001);
+
+	let tall = format!("{}x", "\n".repeat(70_000));
+	let tokens = t(&tall)?;
+	assert_eq!(tokens[0].line(), 70_001);
+
+	Ok(())
+}
@@ -0,0 +1,797 @@
+use std::{
+	cell::RefCell,
+	io::{self, Write},
+	rc::Rc,
+	sync::Arc,
+};
+
+use nyooom_vm::{
+	assembler::assemble,
+	diagnostic::{
+		Diagnostic,
+		ERR_ARITY_MISMATCH,
+		ERR_DIVISION_BY_ZERO,
+		ERR_DUPLICATE_VARIABLE,
+		ERR_INHERIT_SELF,
+		ERR_INPUT,
+		ERR_INVALID_BYTECODE,
+		ERR_INVALID_JUMP,
+		ERR_INVALID_OPERATION,
+		ERR_INVALID_SUPER,
+		ERR_INVALID_THIS,
+		ERR_NOT_CALLABLE,
+		ERR_OUTPUT,
+		ERR_RETURN_FROM_INITIALIZER,
+		ERR_RETURN_OUTSIDE_FUNCTION,
+		ERR_SELF_INITIALIZER,
+		ERR_STACK_OVERFLOW,
+		ERR_STACK_UNDERFLOW,
+		ERR_UNDEFINED_PROPERTY,
+		ERR_UNDEFINED_VARIABLE,
+		ERR_UNKNOWN_INSTRUCTION,
+		ERR_UNSUPPORTED,
+	},
+	instruction::Instruction,
+	parser::Parser,
+	runtime::{RuntimeError, RuntimeErrorKind},
+	vm::VM,
+	ValueType,
+};
+
+type R = Result<(), Box<dyn std::error::Error>>;
+
+fn run(src: &str) -> Result<VM, Box<dyn std::error::Error>> {
+	let mut vm = VM::try_from(Parser::parse(src)?)?;
+	vm.verify().map_err(|problems| problems[0].clone())?;
+	vm.run()?;
+
+	Ok(vm)
+}
+
+fn global(vm: &VM, name: &str) -> Option<String> {
+	vm.global(name).map(ToString::to_string)
+}
+
+fn failed(src: &str) -> RuntimeError {
+	let mut vm = VM::try_from(Parser::parse(src).unwrap()).unwrap();
+	vm.run().unwrap_err()
+}
+
+fn rejected(src: &str) -> Diagnostic {
+	let errors = VM::try_from(Parser::parse(src).unwrap()).err().expect("should not compile");
+	errors[0].clone()
+}
+
+#[test]
+fn test_globals() -> R {
+	let vm = run("let a = 1, b = a + 2;\na = b * 10;")?;
+
+	assert_eq!(global(&vm, "a").as_deref(), Some("30"));
+	assert_eq!(global(&vm, "b").as_deref(), Some("3"));
+	assert_eq!(global(&vm, "c"), None);
+
+	Ok(())
+}
+
+#[test]
+fn test_locals() -> R {
+	let vm = run("let r; { let a = 1; { let a = 2, b = a; r = a + b; } r = r + a; let c = r; r = c = c * 2; }")?;
+
+	assert_eq!(global(&vm, "r").as_deref(), Some("10"));
+	assert_eq!(global(&vm, "a"), None);
+
+	Ok(())
+}
+
+#[test]
+fn test_undefined_variable() {
+	let mut vm = VM::try_from(Parser::parse("let a = 1;\nprint a + b;").unwrap()).unwrap();
+	let error = vm.run().unwrap_err();
+
+	assert_eq!(error.code(), ERR_UNDEFINED_VARIABLE);
+	assert_eq!(error.message(), "undefined variable 'b'");
+	assert_eq!(error.span().unwrap().range(), 21..22);
+
+	let mut vm = VM::try_from(Parser::parse("c = 1;").unwrap()).unwrap();
+	assert_eq!(vm.run().unwrap_err().code(), ERR_UNDEFINED_VARIABLE);
+}
+
+#[test]
+fn test_scope_errors() {
+	let error = rejected("{ let a = 1; { let a = a; } }");
+	assert_eq!(error.code(), ERR_SELF_INITIALIZER);
+	assert_eq!(error.primary().unwrap().span().range(), 23..24);
+
+	let error = rejected("{ let a; let b, a; }");
+	assert_eq!(error.code(), ERR_DUPLICATE_VARIABLE);
+	assert_eq!(error.secondary()[0].span().range(), 6..7);
+}
+
+#[test]
+fn test_unsupported() {
+	// computed members parse, but don't compile yet
+	let error = rejected("let a, b;\nprint a[b];");
+	assert_eq!(error.code(), ERR_UNSUPPORTED);
+	assert_eq!(error.message(), "computed member expressions are not supported yet");
+	assert_eq!(rejected("let a;\na[0] = 1;").code(), ERR_UNSUPPORTED);
+}
+
+#[test]
+fn test_if() -> R {
+	let vm = run("let a = 0, b, c;\nif (a) b = 1; else b = 2;\nif (b) { let d = 3; b = b + d; }\nif (a) c = 1;")?;
+
+	assert_eq!(global(&vm, "b").as_deref(), Some("5"));
+	assert_eq!(global(&vm, "c").as_deref(), Some("null"));
+
+	Ok(())
+}
+
+#[test]
+fn test_loops() -> R {
+	let vm = run("let i = 5, sum = 0;\nwhile (i) { sum = sum + i; i = i - 1; }")?;
+	assert_eq!(global(&vm, "sum").as_deref(), Some("15"));
+
+	let vm = run("let n = 0;\ndo n = n + 1; while (0);\ndo { n = n + 1; } while (n - 5);")?;
+	assert_eq!(global(&vm, "n").as_deref(), Some("5"));
+
+	let vm = run("let f = 1;\nfor (let i = 4; i; i = i - 1) { let g = f * i; f = g; }")?;
+	assert_eq!(global(&vm, "f").as_deref(), Some("24"));
+	assert_eq!(global(&vm, "i"), None);
+
+	Ok(())
+}
+
+#[test]
+fn test_arithmetic() -> R {
+	let vm = run(
+		"let a = 7 % 3, b = -7 % 3, c = 7.5 % 2, d = 2 ** 10, e = 2 ** -1, f = 2.0 ** 0.5 ** 2, g = -2 ** 2;
+let h = 12 & 10, i = 12 | 10, j = 12 ^ 10, k = 1 << 4, l = -16 >> 2, m = true ^ false, n = 1 + 2 << 1;",
+	)?;
+
+	for (name, value) in [
+		("a", "1"),
+		("b", "-1"),
+		("c", "1.5"),
+		("d", "1024"),
+		("e", "0.5"),
+		("f", "1.189207115002721"),
+		("g", "-4"),
+		("h", "8"),
+		("i", "14"),
+		("j", "6"),
+		("k", "16"),
+		("l", "-4"),
+		("m", "true"),
+		("n", "6"),
+	] {
+		assert_eq!(global(&vm, name).as_deref(), Some(value), "{name}");
+	}
+
+	// integers wrap around instead of overflowing
+	let vm = run("let max = 9223372036854775807, a = max + 1, b = -max - 2, c = max * 2, d = a - 1;")?;
+	assert_eq!(global(&vm, "a").as_deref(), Some("-9223372036854775808"));
+	assert_eq!(global(&vm, "b").as_deref(), Some("9223372036854775807"));
+	assert_eq!(global(&vm, "c").as_deref(), Some("-2"));
+	assert_eq!(global(&vm, "d").as_deref(), Some("9223372036854775807"));
+
+	// ints mixed with floats become floats, whichever side they are on
+	let vm = run(
+		"let i = 2, x = 1.5;
+let a = i + x, b = x + i, c = i - x, d = x - i, e = i * x, f = x * i, g = 1 + 2.5, h = 2 * 1.5;",
+	)?;
+	for (name, value) in [
+		("a", "3.5"),
+		("b", "3.5"),
+		("c", "0.5"),
+		("d", "-0.5"),
+		("e", "3"),
+		("f", "3"),
+		("g", "3.5"),
+		("h", "3"),
+	] {
+		assert_eq!(global(&vm, name).as_deref(), Some(value), "{name}");
+	}
+
+	let error = failed("1 % 0;");
+	assert_eq!(error.code(), ERR_DIVISION_BY_ZERO);
+	assert_eq!(error.message(), "cannot divide by 0");
+	assert_eq!(failed("1 / 0;").kind(), &RuntimeErrorKind::DivisionByZero);
+	assert_eq!(failed("1 << 64;").code(), ERR_INVALID_OPERATION);
+	assert_eq!(failed("1 << 64;").message(), "cannot shift 1 by 64 bits");
+	assert_eq!(failed("1 >> -1;").code(), ERR_INVALID_OPERATION);
+	assert_eq!(failed("1.5 & 1;").code(), ERR_INVALID_OPERATION);
+	assert_eq!(failed("1.5 & 1;").message(), "cannot use '&' on float64 and int64");
+	assert_eq!(failed("1.5 << 1;").message(), "cannot use '<<' on float64 and int64");
+	assert_eq!(failed("null | true;").message(), "cannot use '|' on null and bool");
+	assert_eq!(failed("\"a\" ** 2;").code(), ERR_INVALID_OPERATION);
+	assert_eq!(failed("\"a\" ** 2;").message(), "cannot use '**' on string and int64");
+	assert_eq!(failed("null - 1;").message(), "cannot use '-' on null and int64");
+
+	// bools aren't numbers, in either order
+	for op in ["+", "-", "*", "/"] {
+		let message = |a: &str, b: &str| format!("cannot use '{op}' on {a} and {b}");
+		assert_eq!(failed(&format!("1 {op} true;")).message(), message("int64", "bool"));
+		assert_eq!(failed(&format!("true {op} 1;")).message(), message("bool", "int64"));
+		assert_eq!(failed(&format!("1.5 {op} false;")).message(), message("float64", "bool"));
+	}
+
+	Ok(())
+}
+
+#[test]
+fn test_comparisons() -> R {
+	let vm = run(
+		"let a = 1 < 2, b = 2 <= 2.0, c = 3 > 4, d = 3 >= 3, e = 1 == 1.0, f = 1 != \"1\";
+let g = \"abc\" < \"abd\", h = 'b' > 'a', i = null == false, j = null == null;
+let nan = 0.0 / 0.0, k = nan == nan, l = nan < 1, m = nan >= 1;",
+	)?;
+
+	for (name, value) in [
+		("a", "true"),
+		("b", "true"),
+		("c", "false"),
+		("d", "true"),
+		("e", "true"),
+		("f", "true"),
+		("g", "true"),
+		("h", "true"),
+		("i", "false"),
+		("j", "true"),
+		("k", "false"),
+		("l", "false"),
+		("m", "false"),
+	] {
+		assert_eq!(global(&vm, name).as_deref(), Some(value), "{name}");
+	}
+
+	let error = failed("1 < \"a\";");
+	assert_eq!(error.code(), ERR_INVALID_OPERATION);
+	assert_eq!(error.message(), "cannot compare int64 with string");
+	assert_eq!(failed("null >= 'a';").message(), "cannot compare null with char");
+
+	let vm = run("class A {}\nlet x = A(), y = A(), same = x == x, other = x == y, classes = A == A;")?;
+	assert_eq!(global(&vm, "same").as_deref(), Some("true"));
+	assert_eq!(global(&vm, "other").as_deref(), Some("false"));
+	assert_eq!(global(&vm, "classes").as_deref(), Some("true"));
+
+	assert_eq!(failed("null >= null;").code(), ERR_INVALID_OPERATION);
+
+	Ok(())
+}
+
+#[test]
+fn test_unary() -> R {
+	let vm = run("let a = -5, b = -(2.5), c = --a, d = !0, e = !\"\", f = !null, g = ~5, h = ~~a, i = ~true, j = -a * 2;")?;
+
+	for (name, value) in [
+		("a", "-5"),
+		("b", "-2.5"),
+		("c", "-5"),
+		("d", "true"),
+		("e", "false"),
+		("f", "true"),
+		("g", "-6"),
+		("h", "-5"),
+		("i", "false"),
+		("j", "10"),
+	] {
+		assert_eq!(global(&vm, name).as_deref(), Some(value), "{name}");
+	}
+
+	assert_eq!(failed("-\"a\";").code(), ERR_INVALID_OPERATION);
+	assert_eq!(failed("-\"a\";").message(), "cannot use '-' on string");
+	assert_eq!(failed("-null;").code(), ERR_INVALID_OPERATION);
+	assert_eq!(failed("-null;").message(), "cannot use '-' on null");
+	assert_eq!(failed("~1.5;").code(), ERR_INVALID_OPERATION);
+	assert_eq!(failed("~1.5;").message(), "cannot use '~' on float64");
+
+	Ok(())
+}
+
+#[test]
+fn test_logical() -> R {
+	let vm = run("let calls = 0;
+def hit(v) { calls = calls + 1; return v; }
+let a = hit(0) && hit(1), b = hit(2) || hit(3), c = hit(0) || hit(null), d = hit(1) && hit(2);")?;
+
+	assert_eq!(global(&vm, "a").as_deref(), Some("0"));
+	assert_eq!(global(&vm, "b").as_deref(), Some("2"));
+	assert_eq!(global(&vm, "c").as_deref(), Some("null"));
+	assert_eq!(global(&vm, "d").as_deref(), Some("2"));
+	assert_eq!(global(&vm, "calls").as_deref(), Some("6"));
+
+	let vm = run("let n = 0;\nwhile (n < 10 && n != 4) n = n + 1;")?;
+	assert_eq!(global(&vm, "n").as_deref(), Some("4"));
+
+	Ok(())
+}
+
+#[test]
+fn test_rerun() -> R {
+	let mut vm = run("let n = 0;\nfor (let i = 0; i < 1000; i = i + 1) n = n + i;")?;
+	assert_eq!(global(&vm, "n").as_deref(), Some("499500"));
+
+	vm.run()?;
+	assert_eq!(global(&vm, "n").as_deref(), Some("499500"));
+
+	Ok(())
+}
+
+#[test]
+fn test_shared_code() -> R {
+	// PUSH int32 7, HALT
+	let code: Arc<[u8]> = Arc::from([0x01, 0x13, 7, 0, 0, 0, 0x00].as_slice());
+
+	let mut a = VM::new(Arc::clone(&code));
+	let mut b = VM::from(code);
+	assert_eq!(a.run()?, 7);
+	assert_eq!(b.run()?, 7);
+	assert_eq!(a.run()?, 7);
+
+	Ok(())
+}
+
+#[test]
+fn test_instructions() -> R {
+	let mut vm = VM::from(vec![
+		Instruction::Push(ValueType::from(20)),
+		Instruction::Push(ValueType::from(22)),
+		Instruction::Add,
+		Instruction::Halt,
+	]);
+	assert_eq!(vm.run()?, 42);
+
+	let instrs = Parser::parse("print true;")?.toInstructions()?;
+	let mnemonics: Vec<_> = instrs.iter().map(Instruction::mnemonic).collect();
+	assert_eq!(mnemonics, ["PUSH", "PRINT", "HALT"]);
+
+	Ok(())
+}
+
+#[test]
+fn test_corrupted_bytecode() {
+	let error = VM::new(vec![0x01, 0x13, 0x07, 0x00, 0x00, 0x00, 0xff]).run().unwrap_err();
+	assert_eq!(error.code(), ERR_UNKNOWN_INSTRUCTION);
+	assert_eq!(error.kind(), &RuntimeErrorKind::UnknownInstruction(0xff));
+	assert_eq!(error.offset(), 6);
+
+	let error = VM::new(vec![0x01, 0x13, 0x07]).run().unwrap_err();
+	assert_eq!(error.code(), ERR_INVALID_BYTECODE);
+	assert_eq!(
+		error.message(),
+		"unexpected end of bytecode, 3 more bytes expected at offset 2"
+	);
+
+	let error = VM::new(vec![0x01, 0x18, 0x02, 0x00, 0xff, 0xfe, 0x00]).run().unwrap_err();
+	assert_eq!(error.message(), "invalid UTF-8 in string at offset 4");
+
+	// PUSH with a char that isn't a code point
+	let error = VM::new(vec![0x01, 0x17, 0x00, 0xd8, 0x00, 0x00, 0x00]).run().unwrap_err();
+	assert_eq!(error.message(), "invalid character at offset 2");
+
+	let error = VM::new(vec![0x01, 0x7f, 0x00]).run().unwrap_err();
+	assert_eq!(error.message(), "unknown value 0x7f at offset 1");
+
+	// a name index whose operand runs past the end of the program
+	let error = VM::new(vec![0x43, 0xff, 0xff, 0x61]).run().unwrap_err();
+	assert_eq!(error.code(), ERR_INVALID_BYTECODE);
+
+	// LOAD_GLOBAL 2 with an empty string table
+	let error = VM::new(vec![0x43, 0x02, 0x00, 0x00, 0x00, 0x00]).run().unwrap_err();
+	assert_eq!(error.code(), ERR_INVALID_BYTECODE);
+	assert_eq!(error.message(), "string 2 does not exist");
+}
+
+#[test]
+fn test_runtime_errors() {
+	// POP, HALT
+	let mut vm = VM::new(vec![0x02, 0x00]);
+	let error = vm.run().unwrap_err();
+	assert_eq!(error.code(), ERR_STACK_UNDERFLOW);
+	assert_eq!(error.to_string(), "stack underflow (at bytecode offset 0x0000)");
+
+	// PUSH 1i32, PUSH true, HALT
+	let mut vm = VM::new(vec![0x01, 0x13, 0x01, 0x00, 0x00, 0x00, 0x01, 0x11, 0x00]);
+	let error = vm.run().unwrap_err();
+	assert_eq!(error.message(), "cannot use true as exit code");
+	assert_eq!(vm.stack().len(), 1);
+
+	// RETURN outside of a function
+	let mut vm = VM::new(vec![0x01, 0x10, 0x61]);
+	assert_eq!(vm.run().unwrap_err().code(), ERR_INVALID_BYTECODE);
+
+	// the VM keeps its state after an error
+	let mut vm = VM::try_from(Parser::parse("let a = 1;\nlet b = a + null;").unwrap()).unwrap();
+	let error = vm.run().unwrap_err();
+	assert_eq!(error.span().map(|span| span.line()), Some(2));
+	assert_eq!(global(&vm, "a").as_deref(), Some("1"));
+	assert_eq!(vm.stack().len(), 0);
+
+	let diagnostic = Diagnostic::from(error);
+	assert_eq!(diagnostic.code(), ERR_INVALID_OPERATION);
+	assert_eq!(diagnostic.primary().unwrap().message(), "while evaluating this");
+
+	// bad upvalues in programs that weren't verified
+	let invalid = |text: &str| {
+		let error = VM::from(assemble(text).unwrap()).run().unwrap_err();
+		assert_eq!(error.code(), ERR_INVALID_BYTECODE);
+		error.message()
+	};
+
+	let closure = "PUSH null\nPUSH null\nJUMP end\nf:\nLOAD_UPVALUE 0\nRETURN\nend:\n.function f/0 f\nPUSH_FUNCTION 0\nCLOSURE local 1";
+	assert_eq!(
+		invalid(&(closure.replace("UPVALUE 0", "UPVALUE 5") + "\nCALL 0\nHALT")),
+		"upvalue 5 does not exist"
+	);
+	assert_eq!(
+		invalid(&closure.replace("local 1", "local 7")),
+		"cannot capture local slot 7, it is out of range"
+	);
+	// the captured local is popped without closing it
+	assert_eq!(
+		invalid(&format!(
+			"{closure}\nDEFINE_GLOBAL \"f\"\nPOP\nPOP\nLOAD_GLOBAL \"f\"\nCALL 0\nHALT"
+		)),
+		"upvalue points outside of the stack"
+	);
+
+	// the captured local is popped without CLOSE_UPVALUE, so RETURN has nothing to close it over
+	let popped = ".function outer/0 outer\n.function inner/0 inner\nJUMP end
+outer:\nPUSH 1\nPUSH_FUNCTION 1\nCLOSURE local 1\nPOP\nPOP\nPUSH null\nRETURN\ninner:\nPUSH null\nRETURN
+end:\nPUSH_FUNCTION 0\nCALL 0\nPOP\nHALT";
+	assert_eq!(invalid(popped), "upvalue points outside of the stack");
+}
+
+/// Output shared with the test, so it can be read after the VM wrote to it.
+#[derive(Clone, Default)]
+struct Output(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Output {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.0.borrow_mut().write(buf)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+struct Closed;
+
+impl Write for Closed {
+	fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+		Err(io::Error::new(io::ErrorKind::BrokenPipe, "pipe closed"))
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+#[test]
+fn test_output() {
+	let output = Output::default();
+	let src = "let i = 0;\nwhile (i < 3) { print i; print \" \"; i = i + 1; }\nprint \"done\";";
+	let mut vm = VM::try_from(Parser::parse(src).unwrap()).unwrap().withOutput(output.clone());
+	assert_eq!(vm.run(), Ok(0));
+	assert_eq!(String::from_utf8_lossy(&output.0.borrow()), "0 1 2 done");
+
+	let mut vm = VM::try_from(Parser::parse("print 1;").unwrap()).unwrap().withOutput(Closed);
+	let error = vm.run().unwrap_err();
+	assert_eq!(error.code(), ERR_OUTPUT);
+	assert_eq!(error.message(), "cannot write output: pipe closed");
+}
+
+#[test]
+fn test_input() {
+	let vm = |src: &str, input: &'static str| VM::try_from(Parser::parse(src).unwrap()).unwrap().withInput(input.as_bytes());
+
+	let output = Output::default();
+	let src = "let line = readline();\nwhile (line != null) { print line; print \";\"; line = readline(); }";
+	let mut echo = vm(src, "one\r\ntwo\n\nlast").withOutput(output.clone());
+	assert_eq!(echo.run(), Ok(0));
+	assert_eq!(String::from_utf8_lossy(&output.0.borrow()), "one;two;;last;");
+
+	// the prompt is flushed before reading, the echo is still buffered
+	let output = Output::default();
+	let src = "print \"name? \";\nlet name = readline();\nprint name;";
+	let mut prompt = vm(src, "nyooom\n").withOutput(io::BufWriter::new(output.clone()));
+	assert_eq!(prompt.run(), Ok(0));
+	assert_eq!(String::from_utf8_lossy(&output.0.borrow()), "name? ");
+
+	let mut chars = vm(
+		"let a = readchar(), b = readchar(), c = readchar(), d = readline(), e = readline();",
+		"né\n",
+	);
+	assert_eq!(chars.run(), Ok(0));
+	assert_eq!(global(&chars, "a").as_deref(), Some("n"));
+	assert_eq!(global(&chars, "b").as_deref(), Some("é"));
+	assert_eq!(global(&chars, "c").as_deref(), Some("\n"));
+	assert_eq!(global(&chars, "d").as_deref(), Some("null"));
+	assert_eq!(global(&chars, "e").as_deref(), Some("null"));
+
+	let mut invalid = VM::try_from(Parser::parse("readchar();").unwrap())
+		.unwrap()
+		.withInput(&[0xff][..]);
+	let error = invalid.run().unwrap_err();
+	assert_eq!(error.code(), ERR_INPUT);
+	assert_eq!(error.message(), "cannot read input: stream did not contain valid UTF-8");
+}
+
+#[test]
+fn test_traceback() {
+	let src = "def inner(v) {\n  return v / 0;\n}\ndef outer(v) { return inner(v); }\nouter(1);";
+	let mut vm = VM::try_from(Parser::parse(src).unwrap()).unwrap().withFile("main.nyo");
+	let error = vm.run().unwrap_err();
+
+	let sites: Vec<_> = error.backtrace().iter().map(|site| (site.function(), site.line())).collect();
+	assert_eq!(sites, [("<script>", Some(5)), ("outer", Some(4)), ("inner", Some(2))]);
+	assert_eq!(
+		error.traceback(),
+		"Traceback (most recent call last):
+  File \"main.nyo\", line 5, in <script>
+  File \"main.nyo\", line 4, in outer
+  File \"main.nyo\", line 2, in inner
+error[E0412]: cannot divide by 0
+"
+	);
+
+	// recursion is shown once, not once per frame
+	let mut vm = VM::try_from(Parser::parse("def f(n) {\n  return f(n + 1);\n}\nf(0);").unwrap())
+		.unwrap()
+		.withFile("main.nyo");
+	let error = vm.run().unwrap_err();
+	assert_eq!(error.code(), ERR_STACK_OVERFLOW);
+	assert_eq!(error.backtrace().len(), 1025);
+	assert_eq!(
+		error.traceback(),
+		"Traceback (most recent call last):
+  File \"main.nyo\", line 4, in <script>
+  File \"main.nyo\", line 2, in f
+  [Previous line repeated 1023 more times]
+error[E0409]: stack overflow: more than 1024 nested calls
+"
+	);
+
+	// without source locations, the offsets are shown
+	let mut vm = VM::new(vec![0x02, 0x00]);
+	let error = vm.run().unwrap_err();
+	assert_eq!(
+		error.traceback(),
+		"Traceback (most recent call last):\n  File \"<unknown>\", offset 0x0000, in <script>\nerror[E0413]: stack underflow\n"
+	);
+}
+
+#[test]
+fn test_verifier() {
+	let problems = |code: Vec<u8>| VM::new(code).verify().unwrap_err();
+
+	// POP, HALT
+	let p = problems(vec![0x02, 0x00]);
+	assert_eq!(p[0].offset(), 0);
+	assert_eq!(p[0].message(), "stack underflow, POP needs 1 values but the stack only has 0");
+
+	// PUSH null, JUMP +1, HALT
+	let p = problems(vec![0x01, 0x10, 0x50, 0x01, 0x00, 0x00, 0x00, 0x00]);
+	assert_eq!(p[0].offset(), 2);
+	assert_eq!(p[0].message(), "JUMP to offset 8, which is not the start of an instruction");
+
+	// PUSH true, JUMP_IF_FALSE +2, PUSH null, PUSH null, HALT
+	let p = problems(vec![0x01, 0x11, 0x51, 0x02, 0x00, 0x00, 0x00, 0x01, 0x10, 0x01, 0x10, 0x00]);
+	assert_eq!(p[0].offset(), 9);
+	assert_eq!(
+		p[0].message(),
+		"stack depth is 0 on one path to this instruction but 1 on another"
+	);
+
+	// PUSH null, LOAD_LOCAL 1, POP, POP
+	let p = problems(vec![0x01, 0x10, 0x40, 0x01, 0x00, 0x02, 0x02]);
+	assert_eq!(p[0].message(), "local slot 1 is out of range, the frame only has 1 values");
+	assert_eq!(p[1].offset(), 6);
+	assert_eq!(p[1].message(), "program does not end with HALT");
+
+	let p = problems(vec![0x01, 0x13, 0x07]);
+	assert_eq!(
+		p[0].to_string(),
+		"unexpected end of bytecode, 3 more bytes expected at offset 2"
+	);
+
+	// PUSH_CONST 3, HALT
+	let p = problems(vec![0x03, 0x03, 0x00, 0x00, 0x00, 0x00]);
+	assert_eq!(p[0].message(), "constant 3 does not exist, the pool only has 0");
+	let mut vm = VM::new(vec![0x03, 0x03, 0x00, 0x00, 0x00, 0x00]);
+	assert_eq!(vm.run().unwrap_err().code(), ERR_INVALID_BYTECODE);
+
+	// LOAD_GLOBAL 1, PUSH_FUNCTION 0, HALT
+	let p = problems(vec![0x43, 0x01, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00]);
+	assert_eq!(p[0].message(), "string 1 does not exist, the table only has 0");
+	assert_eq!(p[1].offset(), 5);
+	assert_eq!(p[1].message(), "function 0 does not exist, the table only has 0");
+
+	// PUSH null, PUSH null, ADD, HALT
+	assert_eq!(VM::new(vec![0x01, 0x10, 0x01, 0x10, 0x20, 0x00]).verify(), Ok(()));
+
+	let problems = |text: &str| VM::from(assemble(text).unwrap()).verify().unwrap_err();
+
+	// only one upvalue is captured
+	let f = "PUSH null\nJUMP end\nf:\nLOAD_UPVALUE 5\nRETURN\nend:\n.function f/0 f\nPUSH_FUNCTION 0\nCLOSURE local 0\nPOP\nCLOSE_UPVALUE\nHALT";
+	let p = problems(f);
+	assert_eq!(p[0].offset(), 7);
+	assert_eq!(p[0].message(), "upvalue 5 is out of range, the closure only captures 1");
+
+	// f is called without being made a closure
+	let p = problems("JUMP end\nf:\nLOAD_UPVALUE 0\nRETURN\nend:\n.function f/0 f\nPUSH_FUNCTION 0\nPOP\nHALT");
+	assert_eq!(p[0].message(), "upvalue 0 is accessed outside of a closure");
+
+	let p = problems("PUSH null\nSTORE_UPVALUE 0\nPOP\nHALT");
+	assert_eq!(p[0].message(), "upvalue 0 is accessed outside of a closure");
+
+	// g captures an upvalue f doesn't have
+	let g = "PUSH null\nJUMP end\nf:\nJUMP fEnd\ng:\nLOAD_UPVALUE 0\nRETURN\nfEnd:\n.function g/0 g\nPUSH_FUNCTION 0\nCLOSURE upvalue 3\nRETURN
+end:\n.function f/0 f\nPUSH_FUNCTION 1\nCLOSURE local 0\nPOP\nCLOSE_UPVALUE\nHALT";
+	let p = problems(g);
+	assert_eq!(p.len(), 1);
+	assert_eq!(p[0].message(), "upvalue 3 is out of range, the closure only captures 1");
+
+	let p = problems(".function f/0 f\nPUSH_FUNCTION 0\nCLOSURE upvalue 0\nPOP\nHALT\nf:\nPUSH null\nRETURN");
+	assert_eq!(p[0].message(), "upvalue 0 is accessed outside of a closure");
+
+	assert_eq!(
+		VM::from(assemble(&f.replace("UPVALUE 5", "UPVALUE 0")).unwrap()).verify(),
+		Ok(())
+	);
+
+	// a captured local has to be closed, not popped
+	let popped = ".function outer/0 outer\n.function inner/0 inner\nJUMP end
+outer:\nPUSH 1\nPUSH_FUNCTION 1\nCLOSURE local 1\nPOP\nPOP\nPUSH null\nRETURN\ninner:\nPUSH null\nRETURN
+end:\nPUSH_FUNCTION 0\nCALL 0\nPOP\nHALT";
+	let p = problems(popped);
+	assert_eq!(p.len(), 1);
+	assert_eq!(p[0].offset(), 27);
+	assert_eq!(
+		p[0].message(),
+		"POP pops local slot 1, which is captured by a closure, without closing it"
+	);
+
+	// even if only one path captured it
+	let branch = "JUMP start\nf:\nPUSH null\nRETURN\nstart:\nPUSH null\nPUSH true\nJUMP_IF_FALSE end
+.function f/0 f\nPUSH_FUNCTION 0\nCLOSURE local 0\nPOP\nend:\nPOP\nHALT";
+	assert_eq!(problems(branch)[0].message(), "POP pops local slot 0, which is captured by a closure, without closing it");
+	assert_eq!(
+		VM::from(assemble(&branch.replace("end:\nPOP", "end:\nCLOSE_UPVALUE")).unwrap()).verify(),
+		Ok(())
+	);
+}
+
+#[test]
+fn test_invalid_jump() {
+	let mut vm = VM::new(vec![0x50, 0x10, 0x00, 0x00, 0x00, 0x00]);
+	assert_eq!(vm.run().unwrap_err().code(), ERR_INVALID_JUMP);
+}
+
+#[test]
+fn test_functions() -> R {
+	let vm = run("def add(a, b) { let sum = a + b; return sum; }\ndef nothing() {}\nlet r = add(1, 2), n = nothing();")?;
+
+	assert_eq!(global(&vm, "add").as_deref(), Some("<fn add>"));
+	assert_eq!(global(&vm, "r").as_deref(), Some("3"));
+	assert_eq!(global(&vm, "n").as_deref(), Some("null"));
+
+	let vm = run("let r; { let x = 10; def twice(f, v) { return f(f(v)); } def inc(v) { return v + 1; } r = twice(inc, x); }")?;
+	assert_eq!(global(&vm, "r").as_deref(), Some("12"));
+
+	Ok(())
+}
+
+#[test]
+fn test_recursion() -> R {
+	let vm = run("def fib(n) { if (n) { if (n - 1) return fib(n - 1) + fib(n - 2); return 1; } return 0; }\nlet r = fib(15);")?;
+	assert_eq!(global(&vm, "r").as_deref(), Some("610"));
+
+	Ok(())
+}
+
+#[test]
+fn test_call_errors() {
+	let error = failed("def f(a) {}\nf(1, 2);");
+	assert_eq!(error.code(), ERR_ARITY_MISMATCH);
+	assert_eq!(error.message(), "<fn f> expects 1 arguments but got 2");
+	assert_eq!(error.span().unwrap().range(), 12..19);
+
+	assert_eq!(failed("let a = 1;\na();").code(), ERR_NOT_CALLABLE);
+	assert_eq!(failed("def f() { return f(); }\nf();").code(), ERR_STACK_OVERFLOW);
+	assert_eq!(rejected("return 1;").code(), ERR_RETURN_OUTSIDE_FUNCTION);
+}
+
+#[test]
+fn test_closures() -> R {
+	let vm = run(
+		"def makeCounter() { let count = 0; def inc() { count = count + 1; return count; } return inc; }
+let c = makeCounter(), d = makeCounter();
+c(); c();
+let a = c(), b = d();",
+	)?;
+	assert_eq!(global(&vm, "c").as_deref(), Some("<fn inc>"));
+	assert_eq!(global(&vm, "a").as_deref(), Some("3"));
+	assert_eq!(global(&vm, "b").as_deref(), Some("1"));
+
+	let vm = run("let get, set;
+{ let x = 1; def g() { return x; } def s(v) { x = v; } get = g; set = s; }
+set(5);
+let r = get();")?;
+	assert_eq!(global(&vm, "r").as_deref(), Some("5"));
+
+	let vm = run(
+		"def outer(a) { def middle() { def inner() { return a + 1; } return inner; } return middle()(); }
+let r = outer(41), f;
+{ def fact(n) { if (n) return n * fact(n - 1); return 1; } f = fact(5); }",
+	)?;
+	assert_eq!(global(&vm, "r").as_deref(), Some("42"));
+	assert_eq!(global(&vm, "f").as_deref(), Some("120"));
+
+	Ok(())
+}
+
+#[test]
+fn test_classes() -> R {
+	let vm = run("class Point {
+	def init(x, y) { this.x = x; this.y = y; }
+	def sum() { return this.x + this.y; }
+}
+let p = Point(1, 2), s = p.sum(), m = p.sum;
+p.x = 10;
+let t = m(), q = p.init(3, 4);")?;
+
+	assert_eq!(global(&vm, "Point").as_deref(), Some("<class Point>"));
+	assert_eq!(global(&vm, "p").as_deref(), Some("<Point instance>"));
+	assert_eq!(global(&vm, "s").as_deref(), Some("3"));
+	assert_eq!(global(&vm, "m").as_deref(), Some("<fn sum>"));
+	assert_eq!(global(&vm, "t").as_deref(), Some("12"));
+	assert_eq!(global(&vm, "q").as_deref(), Some("<Point instance>"));
+
+	let vm = run("class C { def make() { def f() { return this.v; } return f; } }
+let c = C();
+c.v = 7;
+let r = c.make()();")?;
+	assert_eq!(global(&vm, "r").as_deref(), Some("7"));
+
+	Ok(())
+}
+
+#[test]
+fn test_inheritance() -> R {
+	let vm = run(
+		"class A { def init(n) { this.n = n; } def get() { return this.n; } def name() { return \"A\"; } }
+class B : A { def init(n) { super.init(n + 1); } def name() { return \"B\" + super.name(); } }
+let b = B(1), n = b.get(), name = b.name();",
+	)?;
+
+	assert_eq!(global(&vm, "n").as_deref(), Some("2"));
+	assert_eq!(global(&vm, "name").as_deref(), Some("BA"));
+
+	Ok(())
+}
+
+#[test]
+fn test_class_errors() {
+	assert_eq!(rejected("this;").code(), ERR_INVALID_THIS);
+	assert_eq!(rejected("def f() { return this; }").code(), ERR_INVALID_THIS);
+	assert_eq!(
+		rejected("class A { def f() { return super.f(); } }").code(),
+		ERR_INVALID_SUPER
+	);
+	assert_eq!(rejected("class A : A {}").code(), ERR_INHERIT_SELF);
+	assert_eq!(
+		rejected("class A { def init() { return 1; } }").code(),
+		ERR_RETURN_FROM_INITIALIZER
+	);
+
+	let error = failed("class A {}\nA().b;");
+	assert_eq!(error.code(), ERR_UNDEFINED_PROPERTY);
+	assert_eq!(error.message(), "undefined property 'b' on <A instance>");
+
+	assert_eq!(failed("let a = 1;\na.b;").code(), ERR_INVALID_OPERATION);
+	assert_eq!(failed("let X = 1;\nclass Y : X {}").code(), ERR_INVALID_OPERATION);
+	assert_eq!(failed("class A { def init(a) {} }\nA();").code(), ERR_ARITY_MISMATCH);
+	assert_eq!(failed("class A {}\nA(1);").code(), ERR_ARITY_MISMATCH);
+}